@@ -0,0 +1,103 @@
+use nesem::asm::assembler::assemble;
+use nesem::interp::config::Config;
+use nesem::interp::state::State;
+use std::time::{Duration, Instant};
+
+/// Synthetic loops, each ends with BRK
+const PROGRAMS: [(&str, &str); 3] = [
+    (
+        "register loop",
+        "
+        .org $0600
+            ldy #0
+        outer:
+            ldx #0
+        inner:
+            inx
+            bne inner
+            iny
+            bne outer
+            brk
+        ",
+    ),
+    (
+        "memory copy",
+        "
+        .org $0600
+            ldy #0
+        pass:
+            ldx #0
+        copy:
+            lda $0200,x
+            sta $0300,x
+            inc $0400,x
+            inx
+            bne copy
+            iny
+            cpy #64
+            bne pass
+            brk
+        ",
+    ),
+    (
+        "subroutine calls",
+        "
+        .org $0600
+            ldx #0
+        call:
+            jsr sub
+            inx
+            bne call
+            iny
+            cpy #64
+            bne call
+            brk
+        sub:
+            pha
+            pla
+            rts
+        ",
+    ),
+];
+
+/// Each program is run repeatedly for about this long
+const TARGET: Duration = Duration::from_millis(500);
+
+/// Run @program once from power-on, return the number of executed instructions
+fn run(program: &[u8]) -> usize {
+    let mut state = State::new(&Config::default());
+    state.sp = 0xFD;
+    state.load_program(program, 0x0600);
+    state
+        .run_until_brk(usize::MAX)
+        .expect("benchmark program failed")
+}
+
+// `cargo bench` passes --bench and filters, only the filter is used
+fn main() {
+    let filter = std::env::args().skip(1).find(|a| !a.starts_with('-'));
+    for (name, source) in PROGRAMS.iter() {
+        if matches!(&filter, Some(f) if !name.contains(f.as_str())) {
+            continue;
+        }
+        let program = assemble(source).expect("benchmark program does not assemble");
+        // warm up
+        run(&program);
+
+        let start = Instant::now();
+        let mut instructions = 0;
+        let mut runs = 0;
+        while start.elapsed() < TARGET {
+            instructions += run(&program);
+            runs += 1;
+        }
+        let elapsed = start.elapsed().as_secs_f64();
+        println!(
+            "{:<20} {:>8.2} M instructions/s ({} runs, {} instructions each)",
+            name,
+            instructions as f64 / elapsed / 1e6,
+            runs,
+            instructions / runs
+        );
+    }
+}
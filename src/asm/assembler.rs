@@ -0,0 +1,524 @@
+use crate::instruction::instruction_type::InstructionType;
+use crate::instruction::opcode::{encode, OPCODES};
+use crate::instruction::operand::AddressingMode;
+use std::collections::HashMap;
+
+/// What went wrong on a line of source
+#[derive(Debug, PartialEq)]
+pub enum ErrorKind {
+    UnknownMnemonic(String),
+    UnknownDirective(String),
+    /// Operand syntax isn't valid for the instruction
+    InvalidOperand(String),
+    UnknownLabel(String),
+    DuplicateLabel(String),
+    /// Branch target is further than -128..127 bytes
+    BranchOutOfRange,
+    /// Value doesn't fit into the operand
+    ValueOutOfRange(i64),
+    /// `.org` would move before already assembled code
+    OrgBackwards,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Error {
+    /// 1-based line number
+    pub line: usize,
+    pub kind: ErrorKind,
+}
+
+/// Number or label, resolved in the second pass
+#[derive(Clone, Debug)]
+enum Expr {
+    Number(i64),
+    Label(String),
+}
+
+enum Data {
+    Instruction {
+        code: u8,
+        mode: AddressingMode,
+        operand: Option<Expr>,
+    },
+    Bytes(Vec<Expr>),
+    Words(Vec<Expr>),
+}
+
+/// Assembled item with its address, output of the first pass
+struct Item {
+    line: usize,
+    pc: u16,
+    data: Data,
+}
+
+/// Operand syntax, before the addressing mode is chosen
+enum Syntax {
+    None,
+    Accumulator,
+    Immediate(Expr),
+    /// `addr`, zero page, absolute or relative
+    Plain(Expr),
+    /// `addr,X`
+    X(Expr),
+    /// `addr,Y`
+    Y(Expr),
+    /// `(addr)`
+    Indirect(Expr),
+    /// `(zp,X)`
+    IndexedIndirect(Expr),
+    /// `(zp),Y`
+    IndirectIndexed(Expr),
+}
+
+fn parse_number(s: &str) -> Option<i64> {
+    let (digits, radix) = if let Some(hex) = s.strip_prefix('$') {
+        (hex, 16)
+    } else if let Some(bin) = s.strip_prefix('%') {
+        (bin, 2)
+    } else {
+        (s, 10)
+    };
+    i64::from_str_radix(digits, radix).ok()
+}
+
+fn is_label(s: &str) -> bool {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn parse_expr(s: &str) -> Result<Expr, ErrorKind> {
+    if let Some(n) = parse_number(s) {
+        Ok(Expr::Number(n))
+    } else if is_label(s) {
+        Ok(Expr::Label(String::from(s)))
+    } else {
+        Err(ErrorKind::InvalidOperand(String::from(s)))
+    }
+}
+
+fn parse_operand(s: &str) -> Result<Syntax, ErrorKind> {
+    let compact: String = s.chars().filter(|c| !c.is_whitespace()).collect();
+    let upper = compact.to_ascii_uppercase();
+    let inner = |prefix: usize, suffix: usize| parse_expr(&compact[prefix..compact.len() - suffix]);
+
+    if compact.is_empty() {
+        Ok(Syntax::None)
+    } else if upper == "A" {
+        Ok(Syntax::Accumulator)
+    } else if compact.starts_with('#') {
+        Ok(Syntax::Immediate(inner(1, 0)?))
+    } else if compact.starts_with('(') && upper.ends_with(",X)") {
+        Ok(Syntax::IndexedIndirect(inner(1, 3)?))
+    } else if compact.starts_with('(') && upper.ends_with("),Y") {
+        Ok(Syntax::IndirectIndexed(inner(1, 3)?))
+    } else if compact.starts_with('(') && compact.ends_with(')') {
+        Ok(Syntax::Indirect(inner(1, 1)?))
+    } else if upper.ends_with(",X") {
+        Ok(Syntax::X(inner(0, 2)?))
+    } else if upper.ends_with(",Y") {
+        Ok(Syntax::Y(inner(0, 2)?))
+    } else {
+        Ok(Syntax::Plain(inner(0, 0)?))
+    }
+}
+
+fn parse_mnemonic(s: &str) -> Option<InstructionType> {
+    let upper = s.to_ascii_uppercase();
+    OPCODES
+        .iter()
+        .map(|o| o.ty)
+        .find(|ty| ty.mnemonic() == upper)
+}
+
+/// Value of @expr if it's already known
+fn known_value(expr: &Expr, labels: &HashMap<String, u16>) -> Option<i64> {
+    match expr {
+        Expr::Number(n) => Some(*n),
+        Expr::Label(l) => labels.get(l).map(|v| *v as i64),
+    }
+}
+
+/// Choose zero page or absolute encoding of @ty
+/// Zero page is used only when the address is known to fit, or when it's the only option
+fn zp_or_abs(
+    ty: InstructionType,
+    zp: AddressingMode,
+    abs: AddressingMode,
+    expr: &Expr,
+    labels: &HashMap<String, u16>,
+) -> Option<(AddressingMode, u8)> {
+    let fits_zp = matches!(known_value(expr, labels), Some(v) if (0..0x100).contains(&v));
+    let zp_code = encode(ty, zp).map(|c| (zp, c));
+    let abs_code = encode(ty, abs).map(|c| (abs, c));
+    if fits_zp {
+        zp_code.or(abs_code)
+    } else {
+        abs_code.or(zp_code)
+    }
+}
+
+/// Pick addressing mode and opcode for @ty written with @syntax
+fn select(
+    ty: InstructionType,
+    syntax: Syntax,
+    labels: &HashMap<String, u16>,
+) -> Option<(AddressingMode, u8, Option<Expr>)> {
+    use AddressingMode::*;
+    let with = |mode: AddressingMode, expr: Option<Expr>| encode(ty, mode).map(|c| (mode, c, expr));
+    match syntax {
+        Syntax::None => with(Implicit, None).or_else(|| with(Accumulator, None)),
+        Syntax::Accumulator => with(Accumulator, None),
+        Syntax::Immediate(e) => with(Immediate, Some(e)),
+        Syntax::Indirect(e) => with(Indirect, Some(e)),
+        Syntax::IndexedIndirect(e) => with(IndexedIndirect, Some(e)),
+        Syntax::IndirectIndexed(e) => with(IndirectIndexed, Some(e)),
+        Syntax::Plain(e) => match encode(ty, Relative) {
+            Some(c) => Some((Relative, c, Some(e))),
+            None => zp_or_abs(ty, ZeroPage, Absolute, &e, labels).map(|(m, c)| (m, c, Some(e))),
+        },
+        Syntax::X(e) => {
+            zp_or_abs(ty, ZeroPageX, AbsoluteX, &e, labels).map(|(m, c)| (m, c, Some(e)))
+        }
+        Syntax::Y(e) => {
+            zp_or_abs(ty, ZeroPageY, AbsoluteY, &e, labels).map(|(m, c)| (m, c, Some(e)))
+        }
+    }
+}
+
+fn parse_list(s: &str) -> Result<Vec<Expr>, ErrorKind> {
+    s.split(',').map(|v| parse_expr(v.trim())).collect()
+}
+
+/// Output of the first pass
+struct Program {
+    items: Vec<Item>,
+    labels: HashMap<String, u16>,
+    /// Address of the first assembled byte
+    origin: u16,
+}
+
+/// First pass: parse lines, assign addresses and define labels
+fn first_pass(source: &str) -> Result<Program, Error> {
+    let mut items = Vec::new();
+    let mut labels = HashMap::new();
+    let mut origin = None;
+    let mut pc: u16 = 0;
+
+    for (i, raw) in source.lines().enumerate() {
+        let line = i + 1;
+        let err = |kind| Error { line, kind };
+        let mut text = raw.split(';').next().unwrap_or("").trim();
+
+        // leading label
+        if let Some(colon) = text.find(':') {
+            let name = text[..colon].trim();
+            if is_label(name) {
+                if labels.insert(String::from(name), pc).is_some() {
+                    return Err(err(ErrorKind::DuplicateLabel(String::from(name))));
+                }
+                text = text[colon + 1..].trim();
+            }
+        }
+        if text.is_empty() {
+            continue;
+        }
+
+        let (word, rest) = match text.find(char::is_whitespace) {
+            Some(split) => (&text[..split], text[split..].trim()),
+            None => (text, ""),
+        };
+
+        let data = if let Some(directive) = word.strip_prefix('.') {
+            match directive.to_ascii_lowercase().as_str() {
+                "org" => {
+                    let addr = match parse_expr(rest).map_err(err)? {
+                        Expr::Number(n) if (0..0x10000).contains(&n) => n as u16,
+                        Expr::Number(n) => return Err(err(ErrorKind::ValueOutOfRange(n))),
+                        Expr::Label(l) => return Err(err(ErrorKind::InvalidOperand(l))),
+                    };
+                    if origin.is_some() && addr < pc {
+                        return Err(err(ErrorKind::OrgBackwards));
+                    }
+                    origin.get_or_insert(addr);
+                    pc = addr;
+                    continue;
+                }
+                "byte" => Data::Bytes(parse_list(rest).map_err(err)?),
+                "word" => Data::Words(parse_list(rest).map_err(err)?),
+                _ => return Err(err(ErrorKind::UnknownDirective(String::from(word)))),
+            }
+        } else {
+            let ty = parse_mnemonic(word)
+                .ok_or_else(|| err(ErrorKind::UnknownMnemonic(String::from(word))))?;
+            let syntax = parse_operand(rest).map_err(err)?;
+            let (mode, code, operand) = select(ty, syntax, &labels)
+                .ok_or_else(|| err(ErrorKind::InvalidOperand(String::from(text))))?;
+            Data::Instruction {
+                code,
+                mode,
+                operand,
+            }
+        };
+
+        let size = match &data {
+            Data::Instruction { mode, .. } => 1 + mode.operand_size(),
+            Data::Bytes(v) => v.len(),
+            Data::Words(v) => 2 * v.len(),
+        };
+        origin.get_or_insert(pc);
+        items.push(Item { line, pc, data });
+        pc = pc.wrapping_add(size as u16);
+    }
+
+    Ok(Program {
+        items,
+        labels,
+        origin: origin.unwrap_or(0),
+    })
+}
+
+fn resolve(expr: &Expr, labels: &HashMap<String, u16>) -> Result<i64, ErrorKind> {
+    match expr {
+        Expr::Number(n) => Ok(*n),
+        Expr::Label(l) => labels
+            .get(l)
+            .map(|v| *v as i64)
+            .ok_or_else(|| ErrorKind::UnknownLabel(l.clone())),
+    }
+}
+
+/// Check that @v fits into a byte, negative numbers are twos complement
+fn to_u8(v: i64) -> Result<u8, ErrorKind> {
+    if (-128..0x100).contains(&v) {
+        Ok(v as u8)
+    } else {
+        Err(ErrorKind::ValueOutOfRange(v))
+    }
+}
+
+fn to_u16(v: i64) -> Result<u16, ErrorKind> {
+    if (0..0x10000).contains(&v) {
+        Ok(v as u16)
+    } else {
+        Err(ErrorKind::ValueOutOfRange(v))
+    }
+}
+
+/// Return encoded bytes of @item
+fn emit(item: &Item, labels: &HashMap<String, u16>) -> Result<Vec<u8>, ErrorKind> {
+    let mut out = Vec::new();
+    match &item.data {
+        Data::Instruction {
+            code,
+            mode,
+            operand,
+        } => {
+            out.push(*code);
+            let value = match operand {
+                Some(e) => resolve(e, labels)?,
+                None => return Ok(out),
+            };
+            match mode {
+                AddressingMode::Relative => {
+                    let next = item.pc as i64 + 2;
+                    let offset = value - next;
+                    if !(-128..128).contains(&offset) {
+                        return Err(ErrorKind::BranchOutOfRange);
+                    }
+                    out.push(offset as u8);
+                }
+                m if m.operand_size() == 1 => out.push(to_u8(value)?),
+                _ => out.extend_from_slice(&to_u16(value)?.to_le_bytes()),
+            }
+        }
+        Data::Bytes(values) => {
+            for v in values {
+                out.push(to_u8(resolve(v, labels)?)?);
+            }
+        }
+        Data::Words(values) => {
+            for v in values {
+                out.extend_from_slice(&to_u16(resolve(v, labels)?)?.to_le_bytes());
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Assemble 6502 @source into machine code
+///
+/// Syntax:
+/// - one instruction or directive per line, `;` starts a comment
+/// - `label:` defines a label, optionally followed by an instruction on the same line
+/// - numbers are decimal, `$hex` or `%binary`
+/// - operands: `#imm`, `A`, `addr`, `addr,X`, `addr,Y`, `(addr)`, `(zp,X)`, `(zp),Y`
+/// - directives: `.org addr`, `.byte a, b, ...`, `.word a, b, ...`
+///
+/// Returned code starts at the first `.org` (0 if there is none),
+/// gaps left by later `.org`s are filled with zeros.
+pub fn assemble(source: &str) -> Result<Vec<u8>, Error> {
+    let program = first_pass(source)?;
+
+    let mut out = Vec::new();
+    for item in program.items.iter() {
+        let bytes = emit(item, &program.labels).map_err(|kind| Error {
+            line: item.line,
+            kind,
+        })?;
+        let start = item.pc.wrapping_sub(program.origin) as usize;
+        out.resize(start, 0);
+        out.extend_from_slice(&bytes);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{assemble, Error, ErrorKind};
+
+    #[test]
+    fn addressing_modes() {
+        let code = assemble(
+            "
+            lda #$10
+            lda $10
+            lda $10,x
+            ldx $10,y
+            lda $1234
+            lda $1234,X
+            lda $1234,Y
+            jmp ($1234)
+            lda ($10,x)
+            lda ($10),y
+            asl a
+            asl
+            nop
+            ",
+        )
+        .unwrap();
+        assert_eq!(
+            code,
+            vec![
+                0xA9, 0x10, 0xA5, 0x10, 0xB5, 0x10, 0xB6, 0x10, 0xAD, 0x34, 0x12, 0xBD, 0x34, 0x12,
+                0xB9, 0x34, 0x12, 0x6C, 0x34, 0x12, 0xA1, 0x10, 0xB1, 0x10, 0x0A, 0x0A, 0xEA
+            ]
+        );
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(
+            assemble(".byte 10, $10, %101, -1").unwrap(),
+            vec![10, 0x10, 5, 0xFF]
+        );
+    }
+
+    #[test]
+    fn labels_and_branches() {
+        let code = assemble(
+            "
+            .org $0600
+            start:  ldx #3
+            loop:   dex
+                    bne loop
+                    beq done
+                    jmp start
+            done:   brk
+            ",
+        )
+        .unwrap();
+        assert_eq!(
+            code,
+            vec![0xA2, 0x03, 0xCA, 0xD0, 0xFD, 0xF0, 0x03, 0x4C, 0x00, 0x06, 0x00]
+        );
+    }
+
+    #[test]
+    fn zero_page_labels() {
+        // a zero page label defined before use picks the zero page encoding,
+        // a forward reference has to assume absolute
+        let code = assemble(
+            "
+            .org $00
+            var:  .byte 0
+                  lda var
+                  lda later
+            later: nop
+            ",
+        )
+        .unwrap();
+        assert_eq!(code, vec![0x00, 0xA5, 0x00, 0xAD, 0x06, 0x00, 0xEA]);
+    }
+
+    #[test]
+    fn org_and_words() {
+        let code = assemble(
+            "
+            .org $10
+            nop
+            .org $14
+            vec: .word $1234, vec
+            ",
+        )
+        .unwrap();
+        assert_eq!(code, vec![0xEA, 0, 0, 0, 0x34, 0x12, 0x14, 0x00]);
+    }
+
+    #[test]
+    fn comments_and_case() {
+        assert_eq!(
+            assemble("  LdA #1 ; load one\n; nothing").unwrap(),
+            vec![0xA9, 1]
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let e = |line, kind| Err(Error { line, kind });
+        assert_eq!(
+            assemble("nop\nfoo"),
+            e(2, ErrorKind::UnknownMnemonic(String::from("foo")))
+        );
+        assert_eq!(
+            assemble("jmp nowhere"),
+            e(1, ErrorKind::UnknownLabel(String::from("nowhere")))
+        );
+        assert_eq!(
+            assemble("x:\nx:"),
+            e(2, ErrorKind::DuplicateLabel(String::from("x")))
+        );
+        assert_eq!(assemble("lda #256"), e(1, ErrorKind::ValueOutOfRange(256)));
+        assert_eq!(
+            assemble("sta #1"),
+            e(1, ErrorKind::InvalidOperand(String::from("sta #1")))
+        );
+        assert_eq!(
+            assemble(".org $10\nnop\n.org $0"),
+            e(3, ErrorKind::OrgBackwards)
+        );
+        assert_eq!(
+            assemble(".fill 3"),
+            e(1, ErrorKind::UnknownDirective(String::from(".fill")))
+        );
+    }
+
+    #[test]
+    fn branch_out_of_range() {
+        let mut src = String::from("start: nop\n");
+        for _ in 0..130 {
+            src.push_str("nop\n");
+        }
+        src.push_str("bne start\n");
+        assert_eq!(
+            assemble(&src),
+            Err(Error {
+                line: 132,
+                kind: ErrorKind::BranchOutOfRange
+            })
+        );
+    }
+}
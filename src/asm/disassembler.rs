@@ -0,0 +1,57 @@
+use crate::instruction::instruction::Instruction;
+use crate::instruction::operand::Operand;
+
+/// Return @instruction located at @pc in the syntax accepted by `assemble`, e.g. `LDA #$05`
+/// Branch offsets are shown as the absolute target address
+pub fn format(instruction: &Instruction, pc: u16) -> String {
+    let mnemonic = instruction.get_type().mnemonic();
+    let operand = match *instruction.get_operand() {
+        Operand::Implicit => return String::from(mnemonic),
+        Operand::Accumulator => String::from("A"),
+        Operand::Immediate(v) => format!("#${:02X}", v),
+        Operand::ZeroPage(a) => format!("${:02X}", a),
+        Operand::ZeroPageX(a) => format!("${:02X},X", a),
+        Operand::ZeroPageY(a) => format!("${:02X},Y", a),
+        Operand::Relative(offset) => {
+            format!("${:04X}", pc.wrapping_add(2).wrapping_add(offset as u16))
+        }
+        Operand::Absolute(a) => format!("${:04X}", a),
+        Operand::AbsoluteX(a) => format!("${:04X},X", a),
+        Operand::AbsoluteY(a) => format!("${:04X},Y", a),
+        Operand::Indirect(a) => format!("(${:04X})", a),
+        Operand::IndexedIndirect(a) => format!("(${:02X},X)", a),
+        Operand::IndirectIndexed(a) => format!("(${:02X}),Y", a),
+    };
+    format!("{} {}", mnemonic, operand)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::format;
+    use crate::asm::assembler::assemble;
+    use crate::instruction::instruction::Instruction;
+    use crate::instruction::instruction_type::InstructionType;
+    use crate::instruction::operand::Operand;
+
+    #[test]
+    fn operands() {
+        let lda = |op| format(&Instruction::with_operand(InstructionType::Lda, op), 0);
+        assert_eq!(lda(Operand::Immediate(5)), "LDA #$05");
+        assert_eq!(lda(Operand::ZeroPageX(0x10)), "LDA $10,X");
+        assert_eq!(lda(Operand::AbsoluteY(0x0300)), "LDA $0300,Y");
+        assert_eq!(lda(Operand::IndexedIndirect(0x20)), "LDA ($20,X)");
+        assert_eq!(lda(Operand::IndirectIndexed(0x20)), "LDA ($20),Y");
+        let nop = Instruction::without_operand(InstructionType::Nop);
+        assert_eq!(format(&nop, 0), "NOP");
+        let asl = Instruction::with_operand(InstructionType::Asl, Operand::Accumulator);
+        assert_eq!(format(&asl, 0), "ASL A");
+    }
+
+    #[test]
+    fn branch_target() {
+        let bne = Instruction::with_operand(InstructionType::Bne, Operand::Relative(-2));
+        assert_eq!(format(&bne, 0x0602), "BNE $0602");
+        // output assembles back to the same bytes
+        assert_eq!(assemble(".org $0602\nBNE $0602").unwrap(), vec![0xD0, 0xFE]);
+    }
+}
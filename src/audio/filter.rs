@@ -0,0 +1,188 @@
+use std::f32::consts::PI;
+
+/// Cutoffs of the NES output stage, see https://www.nesdev.org/wiki/APU_Mixer
+pub const NES_HIGH_PASS_1: f32 = 90.0;
+pub const NES_HIGH_PASS_2: f32 = 440.0;
+pub const NES_LOW_PASS: f32 = 14_000.0;
+
+/// Cutoffs are kept below this fraction of the sample rate, the filter is unstable at Nyquist
+const MAX_CUTOFF: f32 = 0.49;
+
+/// Q of a Butterworth filter, flat pass band without resonance
+const BUTTERWORTH_Q: f32 = std::f32::consts::FRAC_1_SQRT_2;
+
+/// Second order IIR filter, direct form I
+/// Coefficients follow the Audio EQ Cookbook, normalized so that a0 is 1
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Biquad {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    /// Previous inputs and outputs, most recent first
+    x: [f32; 2],
+    y: [f32; 2],
+}
+
+impl Biquad {
+    fn new(b: [f32; 3], a: [f32; 3]) -> Biquad {
+        Biquad {
+            b0: b[0] / a[0],
+            b1: b[1] / a[0],
+            b2: b[2] / a[0],
+            a1: a[1] / a[0],
+            a2: a[2] / a[0],
+            x: [0.0; 2],
+            y: [0.0; 2],
+        }
+    }
+
+    /// Return cosine of the cutoff angle and alpha for @cutoff Hz at @sample_rate Hz
+    fn angle(sample_rate: f32, cutoff: f32) -> (f32, f32) {
+        let w0 = 2.0 * PI * cutoff.min(sample_rate * MAX_CUTOFF) / sample_rate;
+        (w0.cos(), w0.sin() / (2.0 * BUTTERWORTH_Q))
+    }
+
+    /// Butterworth high-pass removing frequencies below @cutoff Hz from signal at @sample_rate Hz
+    pub fn high_pass(sample_rate: f32, cutoff: f32) -> Biquad {
+        let (cos, alpha) = Biquad::angle(sample_rate, cutoff);
+        Biquad::new(
+            [(1.0 + cos) / 2.0, -(1.0 + cos), (1.0 + cos) / 2.0],
+            [1.0 + alpha, -2.0 * cos, 1.0 - alpha],
+        )
+    }
+
+    /// Butterworth low-pass removing frequencies above @cutoff Hz from signal at @sample_rate Hz
+    /// @cutoff is capped just below half of @sample_rate
+    pub fn low_pass(sample_rate: f32, cutoff: f32) -> Biquad {
+        let (cos, alpha) = Biquad::angle(sample_rate, cutoff);
+        Biquad::new(
+            [(1.0 - cos) / 2.0, 1.0 - cos, (1.0 - cos) / 2.0],
+            [1.0 + alpha, -2.0 * cos, 1.0 - alpha],
+        )
+    }
+
+    /// Filter one @sample
+    pub fn process(&mut self, sample: f32) -> f32 {
+        let out = self.b0 * sample + self.b1 * self.x[0] + self.b2 * self.x[1]
+            - self.a1 * self.y[0]
+            - self.a2 * self.y[1];
+        self.x = [sample, self.x[0]];
+        self.y = [out, self.y[0]];
+        out
+    }
+
+    /// Forget previous samples, e.g. after seeking or loading a savestate
+    pub fn reset(&mut self) {
+        self.x = [0.0; 2];
+        self.y = [0.0; 2];
+    }
+}
+
+/// Filters applied one after another to the mixer output
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FilterChain {
+    filters: Vec<Biquad>,
+}
+
+impl FilterChain {
+    pub fn new() -> FilterChain {
+        FilterChain {
+            filters: Vec::new(),
+        }
+    }
+
+    /// Output stage of the NES at @sample_rate Hz, two high-passes and a low-pass
+    pub fn nes(sample_rate: f32) -> FilterChain {
+        FilterChain::new()
+            .with(Biquad::high_pass(sample_rate, NES_HIGH_PASS_1))
+            .with(Biquad::high_pass(sample_rate, NES_HIGH_PASS_2))
+            .with(Biquad::low_pass(sample_rate, NES_LOW_PASS))
+    }
+
+    /// Append @filter to the end of the chain
+    pub fn with(mut self, filter: Biquad) -> FilterChain {
+        self.filters.push(filter);
+        self
+    }
+
+    pub fn filters(&self) -> &[Biquad] {
+        &self.filters
+    }
+
+    pub fn process(&mut self, sample: f32) -> f32 {
+        self.filters
+            .iter_mut()
+            .fold(sample, |sample, filter| filter.process(sample))
+    }
+
+    /// Filter @samples in place
+    pub fn process_buffer(&mut self, samples: &mut [f32]) {
+        for sample in samples.iter_mut() {
+            *sample = self.process(*sample);
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.filters.iter_mut().for_each(Biquad::reset);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Biquad, FilterChain};
+    use std::f32::consts::PI;
+
+    const RATE: f32 = 44_100.0;
+
+    /// Return peak output of @chain for a sine of @freq Hz, after it settles
+    fn gain(mut chain: FilterChain, freq: f32) -> f32 {
+        let mut samples: Vec<f32> = (0..RATE as usize)
+            .map(|i| (2.0 * PI * freq * i as f32 / RATE).sin())
+            .collect();
+        chain.process_buffer(&mut samples);
+        samples[samples.len() / 2..]
+            .iter()
+            .fold(0.0, |peak, s| s.abs().max(peak))
+    }
+
+    #[test]
+    fn high_pass() {
+        let hp = FilterChain::new().with(Biquad::high_pass(RATE, 440.0));
+        assert!((gain(hp.clone(), 440.0) - 0.707).abs() < 0.01);
+        assert!(gain(hp.clone(), 5_000.0) > 0.99);
+        assert!(gain(hp, 20.0) < 0.01);
+    }
+
+    #[test]
+    fn low_pass() {
+        let lp = FilterChain::new().with(Biquad::low_pass(RATE, 14_000.0));
+        assert!(gain(lp.clone(), 1_000.0) > 0.99);
+        assert!((gain(lp.clone(), 14_000.0) - 0.707).abs() < 0.01);
+        assert!(gain(lp, 20_000.0) < 0.2);
+    }
+
+    #[test]
+    fn nes_chain() {
+        let mut chain = FilterChain::nes(RATE);
+        assert_eq!(chain.filters().len(), 3);
+        // dc offset of the mixer is removed
+        let tail = (0..RATE as usize)
+            .map(|_| chain.process(1.0))
+            .last()
+            .unwrap();
+        assert!(tail.abs() < 1e-3);
+        assert!(gain(FilterChain::nes(RATE), 2_000.0) > 0.9);
+        chain.reset();
+        assert_eq!(chain, FilterChain::nes(RATE));
+    }
+
+    #[test]
+    fn cutoff_above_nyquist() {
+        let mut lp = Biquad::low_pass(22_050.0, 14_000.0);
+        let out: Vec<f32> = (0..1000).map(|_| lp.process(1.0)).collect();
+        assert!(out.iter().all(|s| s.is_finite()));
+        assert!((out[999] - 1.0).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,148 @@
+use crate::image::png::{self, ColorType};
+
+/// Bytes of one tile, 8 bytes of bit plane 0 followed by 8 bytes of bit plane 1
+pub const TILE_SIZE: usize = 16;
+
+/// Sprite sheets are 16 tiles wide, the layout of a pattern table
+pub const SHEET_COLUMNS: usize = 16;
+
+/// 8x8 pixels of a tile, rows top to bottom, each pixel is a color index 0-3
+pub type Tile = [[u8; 8]; 8];
+
+/// Decode tile stored in 16 @bytes
+pub fn decode_tile(bytes: &[u8; TILE_SIZE]) -> Tile {
+    let mut tile = [[0; 8]; 8];
+    for (y, row) in tile.iter_mut().enumerate() {
+        let (lo, hi) = (bytes[y], bytes[y + 8]);
+        for (x, pixel) in row.iter_mut().enumerate() {
+            // leftmost pixel is in the highest bit
+            let bit = 7 - x;
+            *pixel = (lo >> bit & 1) | (hi >> bit & 1) << 1;
+        }
+    }
+    tile
+}
+
+/// Decode all tiles in @chr, bytes at the end which don't form a whole tile are ignored
+pub fn decode_tiles(chr: &[u8]) -> Vec<Tile> {
+    chr.chunks_exact(TILE_SIZE)
+        .map(|bytes| {
+            let mut tile = [0; TILE_SIZE];
+            tile.copy_from_slice(bytes);
+            decode_tile(&tile)
+        })
+        .collect()
+}
+
+/// RGB image of tiles laid out in rows
+pub struct SpriteSheet {
+    pub width: usize,
+    pub height: usize,
+    /// 3 bytes per pixel, rows top to bottom
+    pub pixels: Vec<u8>,
+}
+
+/// Arrange @tiles left to right in rows of @columns tiles, drawing color index i as @colors[i]
+/// The last row is padded with color 0
+pub fn sprite_sheet(tiles: &[Tile], columns: usize, colors: &[[u8; 3]; 4]) -> SpriteSheet {
+    let columns = columns.max(1);
+    let rows = tiles.len().div_ceil(columns);
+    let width = columns * 8;
+    let height = rows * 8;
+    let mut pixels: Vec<u8> = colors[0]
+        .iter()
+        .copied()
+        .cycle()
+        .take(width * height * 3)
+        .collect();
+    for (i, tile) in tiles.iter().enumerate() {
+        let (left, top) = (i % columns * 8, i / columns * 8);
+        for (y, row) in tile.iter().enumerate() {
+            for (x, &index) in row.iter().enumerate() {
+                let at = ((top + y) * width + left + x) * 3;
+                pixels[at..at + 3].copy_from_slice(&colors[index as usize & 3]);
+            }
+        }
+    }
+    SpriteSheet {
+        width,
+        height,
+        pixels,
+    }
+}
+
+/// Return PNG sprite sheet of all tiles in @chr, `SHEET_COLUMNS` tiles wide, see `sprite_sheet`
+/// Return None if @chr holds no whole tile
+pub fn export_png(chr: &[u8], colors: &[[u8; 3]; 4]) -> Option<Vec<u8>> {
+    let tiles = decode_tiles(chr);
+    if tiles.is_empty() {
+        return None;
+    }
+    let sheet = sprite_sheet(&tiles, SHEET_COLUMNS, colors);
+    Some(png::encode(
+        sheet.width as u32,
+        sheet.height as u32,
+        ColorType::Rgb,
+        &sheet.pixels,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_tiles, export_png, sprite_sheet, Tile};
+
+    /// Tile drawing a `½`, see https://www.nesdev.org/wiki/PPU_pattern_tables
+    const HALF: [u8; 16] = [
+        0x41, 0xC2, 0x44, 0x48, 0x10, 0x20, 0x40, 0x80, 0x01, 0x02, 0x04, 0x08, 0x16, 0x21, 0x42,
+        0x87,
+    ];
+
+    const HALF_PIXELS: Tile = [
+        [0, 1, 0, 0, 0, 0, 0, 3],
+        [1, 1, 0, 0, 0, 0, 3, 0],
+        [0, 1, 0, 0, 0, 3, 0, 0],
+        [0, 1, 0, 0, 3, 0, 0, 0],
+        [0, 0, 0, 3, 0, 2, 2, 0],
+        [0, 0, 3, 0, 0, 0, 0, 2],
+        [0, 3, 0, 0, 0, 0, 2, 0],
+        [3, 0, 0, 0, 0, 2, 2, 2],
+    ];
+
+    const COLORS: [[u8; 3]; 4] = [[0, 0, 0], [255, 0, 0], [0, 255, 0], [0, 0, 255]];
+
+    #[test]
+    fn decode() {
+        let mut chr = HALF.to_vec();
+        chr.extend_from_slice(&[0xFF; 16]);
+        chr.push(0xAA);
+        let tiles = decode_tiles(&chr);
+        assert_eq!(tiles.len(), 2);
+        assert_eq!(tiles[0], HALF_PIXELS);
+        assert_eq!(tiles[1], [[3; 8]; 8]);
+    }
+
+    #[test]
+    fn sheet_layout() {
+        let tiles = [HALF_PIXELS, [[3; 8]; 8], [[1; 8]; 8]];
+        let sheet = sprite_sheet(&tiles, 2, &COLORS);
+        assert_eq!((sheet.width, sheet.height), (16, 16));
+        let pixel = |x: usize, y: usize| {
+            let at = (y * sheet.width + x) * 3;
+            [sheet.pixels[at], sheet.pixels[at + 1], sheet.pixels[at + 2]]
+        };
+        assert_eq!(pixel(7, 0), COLORS[3]);
+        assert_eq!(pixel(1, 0), COLORS[1]);
+        assert_eq!(pixel(8, 0), COLORS[3]);
+        assert_eq!(pixel(0, 8), COLORS[1]);
+        // padding after the last tile
+        assert_eq!(pixel(8, 8), COLORS[0]);
+    }
+
+    #[test]
+    fn png() {
+        let png = export_png(&[0; 8 * 1024], &COLORS).unwrap();
+        // 512 tiles make a 128x256 sheet
+        assert_eq!(png[16..24], [0, 0, 0, 128, 0, 0, 1, 0]);
+        assert_eq!(export_png(&[0; 15], &COLORS), None);
+    }
+}
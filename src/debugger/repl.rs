@@ -0,0 +1,353 @@
+use super::session::{Session, Stop};
+use crate::asm::disassembler;
+use crate::interp::decoder::decode_at;
+use crate::interp::state::{State, RAM_SIZE};
+use std::fmt;
+
+/// Most instructions `continue` runs before giving control back
+const CONTINUE_LIMIT: usize = 10_000_000;
+
+const HELP: &str = "\
+break [addr]        set a breakpoint, list breakpoints without an address
+delete addr         remove a breakpoint
+watch addr          stop when the value at the address changes
+unwatch addr        remove a watchpoint
+step [count]        execute count instructions, 1 by default
+continue            execute until a breakpoint, watchpoint, BRK or idle loop
+x/count addr        dump count bytes, 16 by default
+disasm [addr] [n]   disassemble n instructions, from PC and 10 by default
+regs                show registers
+quit                leave the debugger
+numbers are decimal, $hex or 0xhex, `pc` can be used as an address";
+
+/// Why a command could not be run
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    UnknownCommand(String),
+    /// Argument is not a number, or `pc` where an address is expected
+    InvalidArgument(String),
+    /// Command needs an argument, e.g. an address
+    MissingArgument,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::UnknownCommand(c) => write!(f, "unknown command `{}`, try `help`", c),
+            Error::InvalidArgument(a) => write!(f, "invalid argument `{}`", a),
+            Error::MissingArgument => write!(f, "missing argument"),
+        }
+    }
+}
+
+/// Parse a number written as decimal, `$hex` or `0xhex`
+fn parse_number(text: &str) -> Result<u64, Error> {
+    let parsed = if let Some(hex) = text.strip_prefix('$') {
+        u64::from_str_radix(hex, 16)
+    } else if let Some(hex) = text.strip_prefix("0x") {
+        u64::from_str_radix(hex, 16)
+    } else {
+        text.parse()
+    };
+    parsed.map_err(|_| Error::InvalidArgument(String::from(text)))
+}
+
+/// Parse a 16-bit address written like `parse_number`
+pub fn parse_address(text: &str) -> Result<u16, Error> {
+    match parse_number(text)? {
+        v if v <= 0xFFFF => Ok(v as u16),
+        _ => Err(Error::InvalidArgument(String::from(text))),
+    }
+}
+
+/// Return one disassembled line at @pc, e.g. `0600  A9 05     LDA #$05`, and its size
+/// Bytes which are not an instruction are shown as `.byte`
+fn disasm_line(state: &State, pc: u16) -> (String, u16) {
+    let (text, size) = match decode_at(state, pc) {
+        Ok((instruction, size)) => (disassembler::format(&instruction, pc), size),
+        Err(_) => (format!(".byte ${:02X}", state.cpu_peek(pc)), 1),
+    };
+    let bytes: Vec<String> = (0..size)
+        .map(|i| format!("{:02X}", state.cpu_peek(pc.wrapping_add(i))))
+        .collect();
+    (
+        format!("{:04X}  {:<8}  {}", pc, bytes.join(" "), text),
+        size,
+    )
+}
+
+fn describe(stop: &Stop) -> String {
+    match stop {
+        Stop::Breakpoint { pc } => format!("breakpoint at ${:04X}", pc),
+        Stop::Watch { addr, old, new } => {
+            format!("${:04X} changed from ${:02X} to ${:02X}", addr, old, new)
+        }
+        Stop::Brk { pc } => format!("BRK at ${:04X}", pc),
+        Stop::IdleLoop { pc } => format!("idle loop at ${:04X}", pc),
+        Stop::Error(e) => format!("error: {:?}", e),
+        Stop::StepLimit { steps } => format!("stopped after {} instructions", steps),
+    }
+}
+
+/// Text front end of a `Session`, one command per line, e.g. `break $8000`
+/// See `help` for the list of commands
+pub struct Repl {
+    session: Session,
+}
+
+impl Repl {
+    pub fn new(session: Session) -> Repl {
+        Repl { session }
+    }
+
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+
+    pub fn session_mut(&mut self) -> &mut Session {
+        &mut self.session
+    }
+
+    /// Run the command on @line and return its output, without a trailing newline
+    pub fn command(&mut self, line: &str) -> Result<String, Error> {
+        let mut words = line.split_whitespace();
+        let command = match words.next() {
+            Some(c) => c,
+            None => return Ok(String::new()),
+        };
+        let args: Vec<&str> = words.collect();
+        let (name, suffix) = match command.find('/') {
+            Some(slash) => (&command[..slash], Some(&command[slash + 1..])),
+            None => (command, None),
+        };
+
+        match name {
+            "help" | "h" => Ok(String::from(HELP)),
+            "break" | "b" => match args.first() {
+                Some(a) => {
+                    let addr = self.address(a)?;
+                    self.session.add_breakpoint(addr);
+                    Ok(format!("breakpoint at ${:04X}", addr))
+                }
+                None => {
+                    let list: Vec<String> = self
+                        .session
+                        .breakpoints()
+                        .map(|a| format!("${:04X}", a))
+                        .collect();
+                    Ok(list.join("\n"))
+                }
+            },
+            "delete" | "d" => {
+                let addr = self.address(args.first().ok_or(Error::MissingArgument)?)?;
+                match self.session.remove_breakpoint(addr) {
+                    true => Ok(format!("deleted breakpoint at ${:04X}", addr)),
+                    false => Ok(format!("no breakpoint at ${:04X}", addr)),
+                }
+            }
+            "watch" | "w" => {
+                let addr = self.address(args.first().ok_or(Error::MissingArgument)?)?;
+                self.session.add_watchpoint(addr);
+                let value = self.session.state().cpu_peek(addr);
+                let mut out = format!("watching ${:04X} = ${:02X}", addr, value);
+                if addr as usize >= RAM_SIZE {
+                    out.push_str(", outside ram it never changes until there is an MMU");
+                }
+                Ok(out)
+            }
+            "unwatch" => {
+                let addr = self.address(args.first().ok_or(Error::MissingArgument)?)?;
+                match self.session.remove_watchpoint(addr) {
+                    true => Ok(format!("stopped watching ${:04X}", addr)),
+                    false => Ok(format!("${:04X} is not watched", addr)),
+                }
+            }
+            "step" | "s" => {
+                let count = match args.first() {
+                    Some(c) => parse_number(c)? as usize,
+                    None => 1,
+                };
+                let stop = self.session.step(count);
+                Ok(self.report(stop))
+            }
+            "continue" | "c" => {
+                let stop = self.session.resume(CONTINUE_LIMIT);
+                Ok(self.report(Some(stop)))
+            }
+            "x" => {
+                let count = match suffix {
+                    Some(c) => parse_number(c)? as usize,
+                    None => 16,
+                };
+                let addr = self.address(args.first().ok_or(Error::MissingArgument)?)?;
+                Ok(self.dump(addr, count))
+            }
+            "disasm" | "u" => {
+                let addr = match args.first() {
+                    Some(a) => self.address(a)?,
+                    None => self.session.state().pc,
+                };
+                let count = match args.get(1) {
+                    Some(c) => parse_number(c)? as usize,
+                    None => 10,
+                };
+                Ok(self.disasm(addr, count))
+            }
+            "regs" | "r" => Ok(self.location()),
+            _ => Err(Error::UnknownCommand(String::from(command))),
+        }
+    }
+
+    /// Parse @text as an address, `pc` is the current PC
+    fn address(&self, text: &str) -> Result<u16, Error> {
+        match text {
+            "pc" | "PC" => Ok(self.session.state().pc),
+            _ => parse_address(text),
+        }
+    }
+
+    /// Instruction at PC followed by the registers
+    fn location(&self) -> String {
+        let state = self.session.state();
+        format!("{}\n{}", disasm_line(state, state.pc).0, state)
+    }
+
+    fn report(&self, stop: Option<Stop>) -> String {
+        match stop {
+            Some(stop) => format!("{}\n{}", describe(&stop), self.location()),
+            None => self.location(),
+        }
+    }
+
+    /// Rows of 16 bytes starting at @addr, e.g. `0300: 00 01 02`
+    fn dump(&self, addr: u16, count: usize) -> String {
+        let state = self.session.state();
+        let rows: Vec<String> = (0..count)
+            .step_by(16)
+            .map(|row| {
+                let start = addr.wrapping_add(row as u16);
+                let bytes: Vec<String> = (0..(count - row).min(16))
+                    .map(|i| format!("{:02X}", state.cpu_peek(start.wrapping_add(i as u16))))
+                    .collect();
+                format!("{:04X}: {}", start, bytes.join(" "))
+            })
+            .collect();
+        rows.join("\n")
+    }
+
+    fn disasm(&self, addr: u16, count: usize) -> String {
+        let state = self.session.state();
+        let mut pc = addr;
+        let mut lines = Vec::new();
+        for _ in 0..count {
+            let (line, size) = disasm_line(state, pc);
+            lines.push(line);
+            pc = pc.wrapping_add(size);
+        }
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_address, Error, Repl};
+    use crate::asm::assembler::assemble;
+    use crate::debugger::session::Session;
+    use crate::interp::state::State;
+
+    fn repl() -> Repl {
+        let mut state = State::new_undefined();
+        state.sp = 0xFD;
+        let program = "
+            .org $0600
+                ldx #0
+            loop:
+                inx
+                stx $0300
+                cpx #3
+                bne loop
+                brk
+            ";
+        state.load_program(&assemble(program).unwrap(), 0x0600);
+        Repl::new(Session::new(state))
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(parse_address("$8000"), Ok(0x8000));
+        assert_eq!(parse_address("0x10"), Ok(0x10));
+        assert_eq!(parse_address("42"), Ok(42));
+        assert_eq!(
+            parse_address("$10000"),
+            Err(Error::InvalidArgument(String::from("$10000")))
+        );
+    }
+
+    #[test]
+    fn break_and_continue() {
+        let mut r = repl();
+        assert_eq!(r.command("break $0602").unwrap(), "breakpoint at $0602");
+        assert_eq!(r.command("b").unwrap(), "$0602");
+        assert_eq!(
+            r.command("continue").unwrap(),
+            "breakpoint at $0602\n\
+             0602  E8        INX\n\
+             A:00 X:00 Y:00 P:nv-bdiZc SP:FD PC:0602"
+        );
+        r.command("delete $0602").unwrap();
+        let out = r.command("c").unwrap();
+        assert!(out.starts_with("BRK at $060A\n"), "{}", out);
+    }
+
+    #[test]
+    fn step_and_watch() {
+        let mut r = repl();
+        assert_eq!(
+            r.command("step 2").unwrap(),
+            "0603  8E 00 03  STX $0300\nA:00 X:01 Y:00 P:nv-bdizc SP:FD PC:0603"
+        );
+        assert_eq!(r.command("watch $0300").unwrap(), "watching $0300 = $00");
+        let out = r.command("continue").unwrap();
+        assert!(
+            out.starts_with("$0300 changed from $00 to $01\n"),
+            "{}",
+            out
+        );
+        assert!(r.command("watch $2002").unwrap().contains("outside ram"));
+    }
+
+    #[test]
+    fn memory_and_disassembly() {
+        let mut r = repl();
+        r.session_mut().state_mut().ram_set(0x0301, 0xAB);
+        assert_eq!(
+            r.command("x/18 $0300").unwrap(),
+            "0300: 00 AB 00 00 00 00 00 00 00 00 00 00 00 00 00 00\n0310: 00 00"
+        );
+        assert_eq!(
+            r.command("disasm pc 3").unwrap(),
+            "0600  A2 00     LDX #$00\n\
+             0602  E8        INX\n\
+             0603  8E 00 03  STX $0300"
+        );
+        assert_eq!(
+            r.command("disasm $0608 2").unwrap(),
+            "0608  D0 F8     BNE $0602\n060A  00        BRK"
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let mut r = repl();
+        assert_eq!(
+            r.command("frobnicate"),
+            Err(Error::UnknownCommand(String::from("frobnicate")))
+        );
+        assert_eq!(r.command("watch"), Err(Error::MissingArgument));
+        assert_eq!(
+            r.command("x/zz $0300"),
+            Err(Error::InvalidArgument(String::from("zz")))
+        );
+        assert_eq!(r.command("  ").unwrap(), "");
+    }
+}
@@ -0,0 +1,231 @@
+use crate::interp::error::Error;
+use crate::interp::execution::step;
+use crate::interp::state::{State, BRK_OPCODE};
+use std::collections::BTreeSet;
+
+/// Why a `Session` stopped executing
+#[derive(Debug, PartialEq)]
+pub enum Stop {
+    /// PC reached breakpoint @pc
+    Breakpoint { pc: u16 },
+    /// Value read at @addr changed from @old to @new
+    Watch { addr: u16, old: u8, new: u8 },
+    /// PC points to BRK at @pc, its vector is outside ram until there is an MMU
+    Brk { pc: u16 },
+    /// Instruction at @pc jumps to itself, waiting for an interrupt that never comes
+    IdleLoop { pc: u16 },
+    /// Instruction could not be executed
+    Error(Error),
+    /// @steps instructions ran without any other reason to stop
+    StepLimit { steps: usize },
+}
+
+/// Address whose value is compared after every instruction
+struct Watchpoint {
+    addr: u16,
+    value: u8,
+}
+
+/// Interpreter under control of a debugger, with breakpoints and watchpoints
+pub struct Session {
+    state: State,
+    breakpoints: BTreeSet<u16>,
+    watchpoints: Vec<Watchpoint>,
+}
+
+impl Session {
+    pub fn new(state: State) -> Session {
+        Session {
+            state,
+            breakpoints: BTreeSet::new(),
+            watchpoints: Vec::new(),
+        }
+    }
+
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+
+    pub fn state_mut(&mut self) -> &mut State {
+        &mut self.state
+    }
+
+    /// Stop before executing the instruction at @addr, return false if already set
+    pub fn add_breakpoint(&mut self, addr: u16) -> bool {
+        self.breakpoints.insert(addr)
+    }
+
+    /// Return false if there was no breakpoint at @addr
+    pub fn remove_breakpoint(&mut self, addr: u16) -> bool {
+        self.breakpoints.remove(&addr)
+    }
+
+    /// Breakpoint addresses in ascending order
+    pub fn breakpoints(&self) -> impl Iterator<Item = u16> + '_ {
+        self.breakpoints.iter().copied()
+    }
+
+    /// Stop after an instruction changes the value read at @addr, return false if already set
+    /// Values are peeked, so watching is invisible to the uninit check
+    pub fn add_watchpoint(&mut self, addr: u16) -> bool {
+        if self.watchpoints.iter().any(|w| w.addr == addr) {
+            return false;
+        }
+        let value = self.state.cpu_peek(addr);
+        self.watchpoints.push(Watchpoint { addr, value });
+        true
+    }
+
+    /// Return false if @addr was not watched
+    pub fn remove_watchpoint(&mut self, addr: u16) -> bool {
+        let before = self.watchpoints.len();
+        self.watchpoints.retain(|w| w.addr != addr);
+        self.watchpoints.len() != before
+    }
+
+    /// Watched addresses in the order they were added
+    pub fn watchpoints(&self) -> impl Iterator<Item = u16> + '_ {
+        self.watchpoints.iter().map(|w| w.addr)
+    }
+
+    /// Execute up to @count instructions
+    /// Return why execution stopped early, None if all of them ran.
+    /// A breakpoint at the starting PC is ignored, so stepping can leave it.
+    pub fn step(&mut self, count: usize) -> Option<Stop> {
+        self.run(count, false)
+    }
+
+    /// Execute until there is a reason to stop, at most @max_steps instructions
+    pub fn resume(&mut self, max_steps: usize) -> Stop {
+        self.run(max_steps, true)
+            .unwrap_or(Stop::StepLimit { steps: max_steps })
+    }
+
+    /// Execute up to @count instructions, stopping in idle loops only if @stop_idle
+    fn run(&mut self, count: usize, stop_idle: bool) -> Option<Stop> {
+        for i in 0..count {
+            let pc = self.state.pc;
+            if i > 0 && self.breakpoints.contains(&pc) {
+                return Some(Stop::Breakpoint { pc });
+            }
+            if self.state.cpu_peek(pc) == BRK_OPCODE {
+                return Some(Stop::Brk { pc });
+            }
+            if stop_idle && self.state.is_idle_loop() {
+                return Some(Stop::IdleLoop { pc });
+            }
+            if let Err(e) = step(&mut self.state) {
+                return Some(Stop::Error(e));
+            }
+            if let Some(stop) = self.check_watchpoints() {
+                return Some(stop);
+            }
+        }
+        None
+    }
+
+    /// Return the first watchpoint whose value changed, remembering the new values
+    fn check_watchpoints(&mut self) -> Option<Stop> {
+        let state = &self.state;
+        let mut stop = None;
+        for w in self.watchpoints.iter_mut() {
+            let new = state.cpu_peek(w.addr);
+            if new != w.value && stop.is_none() {
+                stop = Some(Stop::Watch {
+                    addr: w.addr,
+                    old: w.value,
+                    new,
+                });
+            }
+            w.value = new;
+        }
+        stop
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Session, Stop};
+    use crate::asm::assembler::assemble;
+    use crate::interp::error::Error;
+    use crate::interp::state::State;
+
+    fn session(source: &str) -> Session {
+        let mut state = State::new_undefined();
+        state.sp = 0xFF;
+        state.load_program(&assemble(source).unwrap(), 0x0600);
+        Session::new(state)
+    }
+
+    const COUNTER: &str = "
+        .org $0600
+            ldx #0
+        loop:
+            inx
+            stx $10
+            cpx #3
+            bne loop
+            brk
+        ";
+
+    #[test]
+    fn breakpoint() {
+        let mut s = session(COUNTER);
+        assert!(s.add_breakpoint(0x0602));
+        assert!(!s.add_breakpoint(0x0602));
+        assert_eq!(s.resume(100), Stop::Breakpoint { pc: 0x0602 });
+        assert_eq!(s.state().x, 0);
+        // leaving the breakpoint does not stop right away
+        assert_eq!(s.resume(100), Stop::Breakpoint { pc: 0x0602 });
+        assert_eq!(s.state().x, 1);
+        assert!(s.remove_breakpoint(0x0602));
+        assert_eq!(s.resume(100), Stop::Brk { pc: 0x0609 });
+        assert_eq!(s.state().x, 3);
+    }
+
+    #[test]
+    fn watchpoint() {
+        let mut s = session(COUNTER);
+        s.state_mut().ram_set(0x10, 0);
+        assert!(s.add_watchpoint(0x10));
+        assert_eq!(
+            s.resume(100),
+            Stop::Watch {
+                addr: 0x10,
+                old: 0,
+                new: 1
+            }
+        );
+        assert_eq!(s.state().pc, 0x0605);
+        assert!(s.remove_watchpoint(0x10));
+        assert!(!s.remove_watchpoint(0x10));
+    }
+
+    #[test]
+    fn step_count() {
+        let mut s = session(COUNTER);
+        assert_eq!(s.step(2), None);
+        assert_eq!(s.state().x, 1);
+        assert_eq!(s.step(100), Some(Stop::Brk { pc: 0x0609 }));
+    }
+
+    #[test]
+    fn idle_and_errors() {
+        let mut s = session(".org $0600\nhere: jmp here");
+        assert_eq!(s.resume(100), Stop::IdleLoop { pc: 0x0600 });
+        // stepping still runs it
+        assert_eq!(s.step(3), None);
+
+        let mut s = session(".org $0600\nlda $8000");
+        assert_eq!(
+            s.resume(100),
+            Stop::Error(Error::OutOfRam {
+                pc: 0x0600,
+                addr: 0x8000
+            })
+        );
+
+        let mut s = session(".org $0600\nloop: inx\njmp loop");
+        assert_eq!(s.resume(10), Stop::StepLimit { steps: 10 });
+    }
+}
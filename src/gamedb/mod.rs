@@ -0,0 +1 @@
+pub mod ram_map;
@@ -0,0 +1,118 @@
+use crate::interp::state::State;
+use crate::patch::crc32::crc32;
+use crate::ramsearch::value_type::ValueType;
+use std::collections::HashMap;
+
+/// Named value at a known ram address, e.g. number of lives
+pub struct Field {
+    pub name: String,
+    pub addr: u16,
+    pub value_type: ValueType,
+}
+
+/// Layout of interesting values in ram of one game
+#[derive(Default)]
+pub struct RamMap {
+    fields: Vec<Field>,
+}
+
+impl RamMap {
+    pub fn new() -> RamMap {
+        RamMap { fields: Vec::new() }
+    }
+
+    pub fn add(&mut self, name: &str, addr: u16, value_type: ValueType) {
+        self.fields.push(Field {
+            name: String::from(name),
+            addr,
+            value_type,
+        });
+    }
+
+    pub fn fields(&self) -> &[Field] {
+        &self.fields
+    }
+
+    /// Read field @name from @state
+    pub fn read(&self, name: &str, state: &State) -> Option<i64> {
+        let field = self.fields.iter().find(|f| f.name == name)?;
+        field.value_type.read(state.ram(), field.addr as usize)
+    }
+
+    /// Read all fields from @state, in the order they were added
+    pub fn read_all(&self, state: &State) -> Vec<(&str, Option<i64>)> {
+        self.fields
+            .iter()
+            .map(|f| {
+                (
+                    f.name.as_str(),
+                    f.value_type.read(state.ram(), f.addr as usize),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Ram maps registered by the user, keyed by CRC32 of the ROM file
+#[derive(Default)]
+pub struct GameDb {
+    games: HashMap<u32, RamMap>,
+}
+
+impl GameDb {
+    pub fn new() -> GameDb {
+        GameDb {
+            games: HashMap::new(),
+        }
+    }
+
+    pub fn register(&mut self, rom_crc32: u32, map: RamMap) {
+        self.games.insert(rom_crc32, map);
+    }
+
+    /// Return ram map of the game in @rom, if one was registered
+    pub fn lookup(&self, rom: &[u8]) -> Option<&RamMap> {
+        self.games.get(&crc32(rom))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GameDb, RamMap};
+    use crate::interp::state::State;
+    use crate::patch::crc32::crc32;
+    use crate::ramsearch::value_type::ValueType;
+
+    fn sample_map() -> RamMap {
+        let mut map = RamMap::new();
+        map.add("lives", 0x075A, ValueType::U8);
+        map.add("score", 0x07DD, ValueType::Bcd);
+        map
+    }
+
+    #[test]
+    fn read_fields() {
+        let map = sample_map();
+        let mut st = State::new_undefined();
+        st.ram_set(0x075A, 2);
+        st.ram_set(0x07DD, 0x15);
+
+        assert_eq!(map.read("lives", &st), Some(2));
+        assert_eq!(map.read("score", &st), Some(15));
+        assert_eq!(map.read("level", &st), None);
+        assert_eq!(
+            map.read_all(&st),
+            vec![("lives", Some(2)), ("score", Some(15))]
+        );
+    }
+
+    #[test]
+    fn lookup_by_rom() {
+        let rom = b"NES\x1a rom content";
+        let mut db = GameDb::new();
+        db.register(crc32(rom), sample_map());
+
+        assert_eq!(db.lookup(rom).map(|m| m.fields().len()), Some(2));
+        assert!(db.lookup(b"another rom").is_none());
+    }
+}
@@ -0,0 +1,162 @@
+use crate::patch::crc32::crc32;
+
+/// Layout of a pixel
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ColorType {
+    /// 3 bytes per pixel
+    Rgb,
+    /// 4 bytes per pixel, the last one is alpha
+    Rgba,
+}
+
+impl ColorType {
+    pub fn bytes_per_pixel(self) -> usize {
+        match self {
+            ColorType::Rgb => 3,
+            ColorType::Rgba => 4,
+        }
+    }
+
+    /// Color type field of the PNG header
+    fn code(self) -> u8 {
+        match self {
+            ColorType::Rgb => 2,
+            ColorType::Rgba => 6,
+        }
+    }
+}
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+
+/// Largest deflate block which is stored without compression
+const MAX_STORED_BLOCK: usize = 0xFFFF;
+
+/// Adler-32 checksum of @data, as used by zlib
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in data {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    b << 16 | a
+}
+
+/// Wrap @data into a zlib stream of stored deflate blocks
+/// Sprite sheets and screenshots are small, compressing them isn't worth a deflate encoder.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    let mut blocks = data.chunks(MAX_STORED_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        out.extend_from_slice(&[0x01, 0x00, 0x00, 0xFF, 0xFF]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        out.push(last as u8);
+        let len = block.len() as u16;
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn chunk(out: &mut Vec<u8>, name: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(name);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Encode @pixels of a @width x @height image as PNG, rows top to bottom
+/// Both sizes must be nonzero and @pixels must hold exactly `width * height` pixels
+/// of @color_type, otherwise this panics
+pub fn encode(width: u32, height: u32, color_type: ColorType, pixels: &[u8]) -> Vec<u8> {
+    assert!(width > 0 && height > 0, "png: image must not be empty");
+    let row = width as usize * color_type.bytes_per_pixel();
+    assert_eq!(
+        pixels.len(),
+        row * height as usize,
+        "png: pixels do not match the image size"
+    );
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    // 8 bits per channel, deflate, adaptive filtering, no interlace
+    header.extend_from_slice(&[8, color_type.code(), 0, 0, 0]);
+
+    // every row starts with its filter type, 0 leaves it unfiltered
+    let mut raw = Vec::with_capacity((row + 1) * height as usize);
+    for line in pixels.chunks(row) {
+        raw.push(0);
+        raw.extend_from_slice(line);
+    }
+
+    let mut out = SIGNATURE.to_vec();
+    chunk(&mut out, b"IHDR", &header);
+    chunk(&mut out, b"IDAT", &zlib_stored(&raw));
+    chunk(&mut out, b"IEND", &[]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{adler32, encode, zlib_stored, ColorType, SIGNATURE};
+    use crate::patch::crc32::crc32;
+
+    /// Return data of stored blocks in zlib stream @z
+    fn inflate_stored(z: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut pos = 2;
+        loop {
+            let last = z[pos] & 1 == 1;
+            let len = u16::from_le_bytes([z[pos + 1], z[pos + 2]]) as usize;
+            assert_eq!(!len as u16, u16::from_le_bytes([z[pos + 3], z[pos + 4]]));
+            out.extend_from_slice(&z[pos + 5..pos + 5 + len]);
+            pos += 5 + len;
+            if last {
+                break;
+            }
+        }
+        assert_eq!(z[pos..].to_vec(), adler32(&out).to_be_bytes().to_vec());
+        out
+    }
+
+    #[test]
+    fn adler() {
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+        assert_eq!(adler32(b""), 1);
+    }
+
+    #[test]
+    fn stored_blocks() {
+        let data: Vec<u8> = (0..150_000).map(|i| i as u8).collect();
+        assert_eq!(inflate_stored(&zlib_stored(&data)), data);
+        assert!(inflate_stored(&zlib_stored(&[])).is_empty());
+    }
+
+    #[test]
+    fn chunks() {
+        let png = encode(2, 1, ColorType::Rgb, &[255, 0, 0, 0, 0, 255]);
+        assert_eq!(png[..8], SIGNATURE);
+        let mut pos = 8;
+        let mut names = Vec::new();
+        while pos < png.len() {
+            let len = u32::from_be_bytes([png[pos], png[pos + 1], png[pos + 2], png[pos + 3]]);
+            let body = &png[pos + 4..pos + 8 + len as usize];
+            let crc = &png[pos + 8 + len as usize..pos + 12 + len as usize];
+            assert_eq!(crc, crc32(body).to_be_bytes());
+            names.push(String::from_utf8(body[..4].to_vec()).unwrap());
+            if &body[..4] == b"IDAT" {
+                assert_eq!(inflate_stored(&body[4..]), vec![0, 255, 0, 0, 0, 0, 255]);
+            }
+            pos += 12 + len as usize;
+        }
+        assert_eq!(names, vec!["IHDR", "IDAT", "IEND"]);
+        // width 2, height 1, 8 bits of RGB
+        assert_eq!(png[16..29], [0, 0, 0, 2, 0, 0, 0, 1, 8, 2, 0, 0, 0]);
+    }
+}
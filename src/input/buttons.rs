@@ -0,0 +1,101 @@
+/// Button of a standard controller
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Button {
+    A,
+    B,
+    Select,
+    Start,
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Button {
+    /// All buttons in the order the console reads them from $4016
+    pub const ALL: [Button; 8] = [
+        Button::A,
+        Button::B,
+        Button::Select,
+        Button::Start,
+        Button::Up,
+        Button::Down,
+        Button::Left,
+        Button::Right,
+    ];
+
+    /// Bit of the button in `Buttons`
+    pub fn mask(self) -> u8 {
+        1 << self as u8
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Button::A => "a",
+            Button::B => "b",
+            Button::Select => "select",
+            Button::Start => "start",
+            Button::Up => "up",
+            Button::Down => "down",
+            Button::Left => "left",
+            Button::Right => "right",
+        }
+    }
+
+    /// Inverse of `name`, case insensitive
+    pub fn from_name(name: &str) -> Option<Button> {
+        Button::ALL
+            .iter()
+            .copied()
+            .find(|b| b.name().eq_ignore_ascii_case(name))
+    }
+}
+
+/// Pressed buttons of one controller, bit i is `Button::ALL[i]`
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Buttons(pub u8);
+
+impl Buttons {
+    pub fn is_pressed(self, button: Button) -> bool {
+        self.0 & button.mask() != 0
+    }
+
+    pub fn set(&mut self, button: Button, pressed: bool) {
+        if pressed {
+            self.0 |= button.mask();
+        } else {
+            self.0 &= !button.mask();
+        }
+    }
+
+    /// Return @self with @button pressed
+    pub fn with(mut self, button: Button) -> Buttons {
+        self.set(button, true);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Button, Buttons};
+
+    #[test]
+    fn bits() {
+        assert_eq!(Button::A.mask(), 0x01);
+        assert_eq!(Button::Right.mask(), 0x80);
+        let mut b = Buttons::default().with(Button::Start).with(Button::Left);
+        assert_eq!(b, Buttons(0x48));
+        assert!(b.is_pressed(Button::Left));
+        b.set(Button::Left, false);
+        assert!(!b.is_pressed(Button::Left));
+    }
+
+    #[test]
+    fn names() {
+        for b in Button::ALL.iter() {
+            assert_eq!(Button::from_name(b.name()), Some(*b));
+        }
+        assert_eq!(Button::from_name("SELECT"), Some(Button::Select));
+        assert_eq!(Button::from_name("turbo"), None);
+    }
+}
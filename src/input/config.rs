@@ -0,0 +1,399 @@
+use super::buttons::{Button, Buttons};
+
+/// Number of controller ports of the console
+pub const PORTS: usize = 2;
+
+/// Input of the host machine which can be bound to a button, as named by the frontend
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HostInput {
+    /// Keyboard key, e.g. `Key("Z")`, the name can't contain whitespace or `=`
+    Key(String),
+    /// Button @button of gamepad @pad
+    PadButton { pad: u8, button: u8 },
+    /// Axis @axis of gamepad @pad pushed in the @positive or negative direction
+    PadAxis { pad: u8, axis: u8, positive: bool },
+}
+
+/// @input held by the host presses @button of the controller in @port
+#[derive(Clone, Debug, PartialEq)]
+pub struct Binding {
+    pub input: HostInput,
+    pub port: usize,
+    pub button: Button,
+}
+
+/// Named set of bindings, e.g. one per player setup
+#[derive(Clone, Debug, PartialEq)]
+pub struct Profile {
+    pub name: String,
+    pub bindings: Vec<Binding>,
+}
+
+impl Profile {
+    pub fn new(name: &str) -> Profile {
+        Profile {
+            name: String::from(name),
+            bindings: Vec::new(),
+        }
+    }
+
+    /// Make @input press @button in @port, an input may press several buttons
+    /// @port must be below `PORTS`, otherwise this panics
+    pub fn bind(&mut self, input: HostInput, port: usize, button: Button) {
+        assert!(port < PORTS, "bind: there is no port {}", port);
+        let binding = Binding {
+            input,
+            port,
+            button,
+        };
+        if !self.bindings.contains(&binding) {
+            self.bindings.push(binding);
+        }
+    }
+
+    /// Remove all bindings of @input
+    pub fn unbind(&mut self, input: &HostInput) {
+        self.bindings.retain(|b| b.input != *input);
+    }
+
+    /// Return the port and button of every binding of @input
+    pub fn lookup<'a>(
+        &'a self,
+        input: &'a HostInput,
+    ) -> impl Iterator<Item = (usize, Button)> + 'a {
+        self.bindings
+            .iter()
+            .filter(move |b| b.input == *input)
+            .map(|b| (b.port, b.button))
+    }
+}
+
+/// What went wrong on a line of the text format
+#[derive(Debug, PartialEq)]
+pub enum ErrorKind {
+    /// Line is not a profile header, a binding or the active profile
+    Syntax(String),
+    UnknownButton(String),
+    InvalidPort(String),
+    /// Binding comes before the first profile header
+    NoProfile,
+    DuplicateProfile(String),
+    /// Active profile is not defined anywhere
+    UnknownProfile(String),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Error {
+    /// 1-based line number
+    pub line: usize,
+    pub kind: ErrorKind,
+}
+
+/// Bindings of host inputs to controller buttons, with several profiles of which one is active
+///
+/// The text format has one entry per line, `#` starts a comment:
+/// ```text
+/// active = default
+/// [default]
+/// key Z = 0 a
+/// pad 0 button 1 = 1 b
+/// pad 0 axis 1 + = 0 down
+/// ```
+/// A binding is a host input, `=`, the port starting from 0 and the button name.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InputConfig {
+    profiles: Vec<Profile>,
+    active: usize,
+}
+
+impl Default for InputConfig {
+    /// Single profile `default` with arrow keys, Z, X, Shift and Enter on the first port
+    fn default() -> InputConfig {
+        let mut profile = Profile::new("default");
+        let keys = [
+            ("Z", Button::B),
+            ("X", Button::A),
+            ("RShift", Button::Select),
+            ("Return", Button::Start),
+            ("Up", Button::Up),
+            ("Down", Button::Down),
+            ("Left", Button::Left),
+            ("Right", Button::Right),
+        ];
+        for (key, button) in keys.iter() {
+            profile.bind(HostInput::Key(String::from(*key)), 0, *button);
+        }
+        InputConfig {
+            profiles: vec![profile],
+            active: 0,
+        }
+    }
+}
+
+impl InputConfig {
+    /// Create a config where @profile is the only profile, and active
+    pub fn new(profile: Profile) -> InputConfig {
+        InputConfig {
+            profiles: vec![profile],
+            active: 0,
+        }
+    }
+
+    pub fn profiles(&self) -> &[Profile] {
+        &self.profiles
+    }
+
+    /// Add @profile, replacing a profile of the same name
+    pub fn add_profile(&mut self, profile: Profile) {
+        match self.profiles.iter().position(|p| p.name == profile.name) {
+            Some(i) => self.profiles[i] = profile,
+            None => self.profiles.push(profile),
+        }
+    }
+
+    pub fn profile_mut(&mut self, name: &str) -> Option<&mut Profile> {
+        self.profiles.iter_mut().find(|p| p.name == name)
+    }
+
+    /// Make profile @name active, return false if there is none
+    pub fn select(&mut self, name: &str) -> bool {
+        match self.profiles.iter().position(|p| p.name == name) {
+            Some(i) => {
+                self.active = i;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn active(&self) -> &Profile {
+        &self.profiles[self.active]
+    }
+
+    /// Update @ports after the host reports @input as @pressed or released
+    /// Return false if @input is not bound in the active profile
+    pub fn apply(&self, input: &HostInput, pressed: bool, ports: &mut [Buttons; PORTS]) -> bool {
+        let mut bound = false;
+        for (port, button) in self.active().lookup(input) {
+            ports[port].set(button, pressed);
+            bound = true;
+        }
+        bound
+    }
+
+    /// Write the config in the text format, readable by `from_text`
+    pub fn to_text(&self) -> String {
+        let mut out = format!("active = {}\n", self.active().name);
+        for profile in self.profiles.iter() {
+            out.push_str(&format!("[{}]\n", profile.name));
+            for b in profile.bindings.iter() {
+                let input = match &b.input {
+                    HostInput::Key(key) => format!("key {}", key),
+                    HostInput::PadButton { pad, button } => {
+                        format!("pad {} button {}", pad, button)
+                    }
+                    HostInput::PadAxis {
+                        pad,
+                        axis,
+                        positive,
+                    } => format!(
+                        "pad {} axis {} {}",
+                        pad,
+                        axis,
+                        if *positive { '+' } else { '-' }
+                    ),
+                };
+                out.push_str(&format!("{} = {} {}\n", input, b.port, b.button.name()));
+            }
+        }
+        out
+    }
+
+    /// Read config written in the text format, see `InputConfig`
+    /// Without an `active` line, the first profile is active
+    pub fn from_text(text: &str) -> Result<InputConfig, Error> {
+        let mut profiles: Vec<Profile> = Vec::new();
+        let mut active = None;
+        for (i, raw) in text.lines().enumerate() {
+            let err = |kind| Error { line: i + 1, kind };
+            let line = raw.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                let name = name.trim();
+                if profiles.iter().any(|p| p.name == name) {
+                    return Err(err(ErrorKind::DuplicateProfile(String::from(name))));
+                }
+                profiles.push(Profile::new(name));
+                continue;
+            }
+            let syntax = || err(ErrorKind::Syntax(String::from(line)));
+            let (lhs, rhs) = match line.find('=') {
+                Some(eq) => (line[..eq].trim(), line[eq + 1..].trim()),
+                None => return Err(syntax()),
+            };
+            if lhs == "active" {
+                active = Some((i + 1, String::from(rhs)));
+                continue;
+            }
+            let input = parse_input(lhs).ok_or_else(syntax)?;
+            let (port, button) = match rhs.split_whitespace().collect::<Vec<_>>()[..] {
+                [port, button] => (port, button),
+                _ => return Err(syntax()),
+            };
+            let port = match port.parse() {
+                Ok(p) if p < PORTS => p,
+                _ => return Err(err(ErrorKind::InvalidPort(String::from(port)))),
+            };
+            let button = Button::from_name(button)
+                .ok_or_else(|| err(ErrorKind::UnknownButton(String::from(button))))?;
+            let profile = profiles
+                .last_mut()
+                .ok_or_else(|| err(ErrorKind::NoProfile))?;
+            profile.bind(input, port, button);
+        }
+
+        if profiles.is_empty() {
+            return Err(Error {
+                line: text.lines().count(),
+                kind: ErrorKind::NoProfile,
+            });
+        }
+        let mut config = InputConfig {
+            profiles,
+            active: 0,
+        };
+        if let Some((line, name)) = active {
+            if !config.select(&name) {
+                return Err(Error {
+                    line,
+                    kind: ErrorKind::UnknownProfile(name),
+                });
+            }
+        }
+        Ok(config)
+    }
+}
+
+/// Parse host input written as `key NAME`, `pad N button N` or `pad N axis N +`
+fn parse_input(text: &str) -> Option<HostInput> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    match words[..] {
+        ["key", name] => Some(HostInput::Key(String::from(name))),
+        ["pad", pad, "button", button] => Some(HostInput::PadButton {
+            pad: pad.parse().ok()?,
+            button: button.parse().ok()?,
+        }),
+        ["pad", pad, "axis", axis, direction] => Some(HostInput::PadAxis {
+            pad: pad.parse().ok()?,
+            axis: axis.parse().ok()?,
+            positive: match direction {
+                "+" => true,
+                "-" => false,
+                _ => return None,
+            },
+        }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Error, ErrorKind, HostInput, InputConfig, Profile};
+    use crate::input::buttons::{Button, Buttons};
+
+    fn key(name: &str) -> HostInput {
+        HostInput::Key(String::from(name))
+    }
+
+    #[test]
+    fn apply_events() {
+        let config = InputConfig::default();
+        let mut ports = [Buttons::default(); 2];
+        assert!(config.apply(&key("X"), true, &mut ports));
+        assert!(config.apply(&key("Up"), true, &mut ports));
+        assert_eq!(ports[0], Buttons(0x11));
+        config.apply(&key("X"), false, &mut ports);
+        assert_eq!(ports[0], Buttons(0x10));
+        assert!(!config.apply(&key("F1"), true, &mut ports));
+    }
+
+    #[test]
+    fn profiles() {
+        let mut config = InputConfig::default();
+        let mut two = Profile::new("two players");
+        two.bind(HostInput::PadButton { pad: 1, button: 0 }, 1, Button::A);
+        two.bind(key("Space"), 0, Button::A);
+        two.bind(key("Space"), 1, Button::A);
+        config.add_profile(two);
+        assert!(config.select("two players"));
+        assert!(!config.select("three players"));
+
+        let mut ports = [Buttons::default(); 2];
+        config.apply(&key("Space"), true, &mut ports);
+        assert_eq!(ports, [Buttons(0x01), Buttons(0x01)]);
+        config
+            .profile_mut("two players")
+            .unwrap()
+            .unbind(&key("Space"));
+        assert_eq!(config.active().bindings.len(), 1);
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let mut config = InputConfig::default();
+        let mut pad = Profile::new("pad");
+        pad.bind(HostInput::PadButton { pad: 0, button: 3 }, 1, Button::Start);
+        let axis = HostInput::PadAxis {
+            pad: 0,
+            axis: 1,
+            positive: false,
+        };
+        pad.bind(axis, 1, Button::Up);
+        config.add_profile(pad);
+        config.select("pad");
+
+        let text = config.to_text();
+        assert!(text.starts_with("active = pad\n[default]\nkey Z = 0 b\n"));
+        assert!(text.ends_with("[pad]\npad 0 button 3 = 1 start\npad 0 axis 1 - = 1 up\n"));
+        assert_eq!(InputConfig::from_text(&text), Ok(config));
+    }
+
+    #[test]
+    fn text_comments_and_default_active() {
+        let config = InputConfig::from_text("# mine\n[p1]\nkey A = 0 left # left hand\n").unwrap();
+        assert_eq!(config.active().name, "p1");
+        assert_eq!(config.active().bindings.len(), 1);
+    }
+
+    #[test]
+    fn text_errors() {
+        let error = |text: &str| InputConfig::from_text(text).unwrap_err();
+        assert_eq!(
+            error("[p]\nkey A = 0 turbo"),
+            Error {
+                line: 2,
+                kind: ErrorKind::UnknownButton(String::from("turbo"))
+            }
+        );
+        assert_eq!(
+            error("[p]\nkey A = 2 a").kind,
+            ErrorKind::InvalidPort(String::from("2"))
+        );
+        assert_eq!(error("key A = 0 a").kind, ErrorKind::NoProfile);
+        assert_eq!(
+            error("[p]\n[p]").kind,
+            ErrorKind::DuplicateProfile(String::from("p"))
+        );
+        assert_eq!(
+            error("active = q\n[p]").kind,
+            ErrorKind::UnknownProfile(String::from("q"))
+        );
+        assert_eq!(
+            error("[p]\npad x button 1 = 0 a").kind,
+            ErrorKind::Syntax(String::from("pad x button 1 = 0 a"))
+        );
+        assert_eq!(error("").kind, ErrorKind::NoProfile);
+    }
+}
@@ -0,0 +1,121 @@
+use super::buttons::Buttons;
+
+/// Sequence of button states, each held for a number of frames
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Macro {
+    steps: Vec<(Buttons, u32)>,
+}
+
+impl Macro {
+    pub fn new() -> Macro {
+        Macro::default()
+    }
+
+    /// Append @buttons held for @frames frames
+    pub fn then(mut self, buttons: Buttons, frames: u32) -> Macro {
+        if frames > 0 {
+            self.steps.push((buttons, frames));
+        }
+        self
+    }
+
+    pub fn steps(&self) -> &[(Buttons, u32)] {
+        &self.steps
+    }
+
+    /// Number of frames it takes to play the macro once
+    pub fn frames(&self) -> u64 {
+        self.steps.iter().map(|(_, f)| *f as u64).sum()
+    }
+}
+
+/// Plays a `Macro` one frame at a time on top of the buttons held by the player
+#[derive(Clone, Debug, PartialEq)]
+pub struct MacroPlayer {
+    input: Macro,
+    /// Start over after the last step
+    looping: bool,
+    step: usize,
+    frame: u32,
+}
+
+impl MacroPlayer {
+    pub fn new(input: Macro, looping: bool) -> MacroPlayer {
+        MacroPlayer {
+            input,
+            looping,
+            step: 0,
+            frame: 0,
+        }
+    }
+
+    /// Return true once a non-looping macro played all of its steps
+    pub fn is_finished(&self) -> bool {
+        self.step >= self.input.steps.len()
+    }
+
+    /// Start playing from the first step again
+    pub fn restart(&mut self) {
+        self.step = 0;
+        self.frame = 0;
+    }
+
+    /// Return buttons for this frame, the macro step combined with @held
+    /// Call once per frame. After the macro finishes, @held is returned unchanged.
+    pub fn next(&mut self, held: Buttons) -> Buttons {
+        if self.is_finished() && self.looping {
+            self.restart();
+        }
+        let (buttons, frames) = match self.input.steps.get(self.step) {
+            Some(step) => *step,
+            None => return held,
+        };
+        self.frame += 1;
+        if self.frame >= frames {
+            self.step += 1;
+            self.frame = 0;
+        }
+        Buttons(held.0 | buttons.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Macro, MacroPlayer};
+    use crate::input::buttons::{Button, Buttons};
+
+    fn hadouken() -> Macro {
+        let down = Buttons::default().with(Button::Down);
+        Macro::new()
+            .then(down, 2)
+            .then(down.with(Button::Right), 1)
+            .then(Buttons::default(), 0)
+            .then(Buttons::default().with(Button::Right).with(Button::B), 1)
+    }
+
+    #[test]
+    fn play_once() {
+        let m = hadouken();
+        assert_eq!(m.steps().len(), 3);
+        assert_eq!(m.frames(), 4);
+        let mut p = MacroPlayer::new(m, false);
+        let start = Buttons::default().with(Button::Start);
+        let frames: Vec<u8> = (0..6).map(|_| p.next(start).0).collect();
+        assert_eq!(frames, vec![0x28, 0x28, 0xA8, 0x8A, 0x08, 0x08]);
+        assert!(p.is_finished());
+        p.restart();
+        assert_eq!(p.next(Buttons::default()), Buttons(0x20));
+    }
+
+    #[test]
+    fn looping() {
+        let m = Macro::new()
+            .then(Buttons::default().with(Button::A), 1)
+            .then(Buttons::default(), 1);
+        let mut p = MacroPlayer::new(m, true);
+        let frames: Vec<u8> = (0..5).map(|_| p.next(Buttons::default()).0).collect();
+        assert_eq!(frames, vec![1, 0, 1, 0, 1]);
+        let mut empty = MacroPlayer::new(Macro::new(), true);
+        assert_eq!(empty.next(Buttons(0x10)), Buttons(0x10));
+    }
+}
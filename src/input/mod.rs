@@ -0,0 +1,4 @@
+pub mod buttons;
+pub mod config;
+pub mod macros;
+pub mod turbo;
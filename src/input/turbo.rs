@@ -0,0 +1,91 @@
+use super::buttons::{Button, Buttons};
+
+/// Timing of a turbo button, repeating @on frames pressed and @off frames released
+/// Frequency is `frame rate / (on + off)` and duty cycle `on / (on + off)`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Turbo {
+    pub on: u32,
+    pub off: u32,
+}
+
+impl Default for Turbo {
+    /// 30 presses per second at 60 frames per second
+    fn default() -> Turbo {
+        Turbo { on: 1, off: 1 }
+    }
+}
+
+/// Turns held buttons with turbo enabled into repeated presses
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TurboButtons {
+    turbo: [Option<Turbo>; 8],
+    /// Frames each button has been held for
+    held_for: [u32; 8],
+}
+
+impl TurboButtons {
+    pub fn new() -> TurboButtons {
+        TurboButtons::default()
+    }
+
+    /// Repeat @button with @turbo timing while it's held, None makes it a plain button
+    pub fn set(&mut self, button: Button, turbo: Option<Turbo>) {
+        self.turbo[button as usize] = turbo;
+    }
+
+    pub fn get(&self, button: Button) -> Option<Turbo> {
+        self.turbo[button as usize]
+    }
+
+    /// Return buttons the console sees this frame while the player holds @held
+    /// Call once per frame. A press always starts with the pressed part of the cycle.
+    pub fn apply(&mut self, held: Buttons) -> Buttons {
+        let mut out = held;
+        for button in Button::ALL.iter().copied() {
+            let i = button as usize;
+            if !held.is_pressed(button) {
+                self.held_for[i] = 0;
+                continue;
+            }
+            if let Some(t) = self.turbo[i] {
+                let period = (t.on + t.off).max(1);
+                out.set(button, self.held_for[i] % period < t.on);
+            }
+            self.held_for[i] = self.held_for[i].wrapping_add(1);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Turbo, TurboButtons};
+    use crate::input::buttons::{Button, Buttons};
+
+    #[test]
+    fn duty_cycle() {
+        let mut t = TurboButtons::new();
+        t.set(Button::A, Some(Turbo { on: 2, off: 1 }));
+        let held = Buttons::default().with(Button::A).with(Button::B);
+        let a: Vec<bool> = (0..7)
+            .map(|_| t.apply(held).is_pressed(Button::A))
+            .collect();
+        assert_eq!(a, vec![true, true, false, true, true, false, true]);
+        // plain buttons are unaffected
+        assert!(t.apply(held).is_pressed(Button::B));
+    }
+
+    #[test]
+    fn release_restarts_cycle() {
+        let mut t = TurboButtons::new();
+        t.set(Button::B, Some(Turbo::default()));
+        let held = Buttons::default().with(Button::B);
+        assert_eq!(t.apply(held), held);
+        assert_eq!(t.apply(held), Buttons::default());
+        assert_eq!(t.apply(Buttons::default()), Buttons::default());
+        assert_eq!(t.apply(held), held);
+        t.set(Button::B, None);
+        assert_eq!(t.apply(held), held);
+        assert_eq!(t.get(Button::B), None);
+    }
+}
@@ -3,7 +3,7 @@
 /// Type of instruction
 /// See http://6502.org/tutorials/6502opcodes.html
 /// See http://obelisk.me.uk/6502/reference.html
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum InstructionType {
     /// Add with carry
     /// Affects: `NVZC`
@@ -145,4 +145,83 @@ pub enum InstructionType {
     Stx,
     /// Store Y register
     Sty,
+    /// Unofficial, AND X and immediate into A mixed with an unstable constant
+    /// Affects: `NZ`
+    Xaa,
+    /// Unofficial, store A AND X AND (high byte of address + 1)
+    Ahx,
+    /// Unofficial, transfer A AND X to SP, then store it like `Ahx`
+    Tas,
+    /// Unofficial, load memory AND SP into A, X and SP
+    /// Affects: `NZ`
+    Las,
+}
+
+impl InstructionType {
+    /// Return assembler mnemonic, e.g. `"LDA"`
+    pub fn mnemonic(self) -> &'static str {
+        use InstructionType::*;
+        match self {
+            Adc => "ADC",
+            And => "AND",
+            Asl => "ASL",
+            Bit => "BIT",
+            Bpl => "BPL",
+            Bmi => "BMI",
+            Bvc => "BVC",
+            Bvs => "BVS",
+            Bcc => "BCC",
+            Bcs => "BCS",
+            Bne => "BNE",
+            Beq => "BEQ",
+            Brk => "BRK",
+            Cmp => "CMP",
+            Cpx => "CPX",
+            Cpy => "CPY",
+            Dec => "DEC",
+            Eor => "EOR",
+            Clc => "CLC",
+            Sec => "SEC",
+            Cli => "CLI",
+            Sei => "SEI",
+            Clv => "CLV",
+            Cld => "CLD",
+            Sed => "SED",
+            Inc => "INC",
+            Jmp => "JMP",
+            Jsr => "JSR",
+            Lda => "LDA",
+            Ldx => "LDX",
+            Ldy => "LDY",
+            Lsr => "LSR",
+            Nop => "NOP",
+            Ora => "ORA",
+            Tax => "TAX",
+            Txa => "TXA",
+            Dex => "DEX",
+            Inx => "INX",
+            Tay => "TAY",
+            Tya => "TYA",
+            Dey => "DEY",
+            Iny => "INY",
+            Rol => "ROL",
+            Ror => "ROR",
+            Rti => "RTI",
+            Rts => "RTS",
+            Sbc => "SBC",
+            Sta => "STA",
+            Txs => "TXS",
+            Tsx => "TSX",
+            Pha => "PHA",
+            Pla => "PLA",
+            Php => "PHP",
+            Plp => "PLP",
+            Stx => "STX",
+            Sty => "STY",
+            Xaa => "XAA",
+            Ahx => "AHX",
+            Tas => "TAS",
+            Las => "LAS",
+        }
+    }
 }
@@ -1,3 +1,4 @@
 pub mod instruction;
 pub mod instruction_type;
+pub mod opcode;
 pub mod operand;
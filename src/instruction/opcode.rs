@@ -0,0 +1,254 @@
+use super::instruction_type::InstructionType;
+use super::instruction_type::InstructionType::*;
+use super::operand::AddressingMode;
+use super::operand::AddressingMode::*;
+
+/// Encoding of an instruction
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Opcode {
+    pub code: u8,
+    pub ty: InstructionType,
+    pub mode: AddressingMode,
+}
+
+const fn op(code: u8, ty: InstructionType, mode: AddressingMode) -> Opcode {
+    Opcode { code, ty, mode }
+}
+
+/// All official opcodes, sorted by `code`
+/// See http://obelisk.me.uk/6502/reference.html
+pub const OPCODES: [Opcode; 151] = [
+    op(0x00, Brk, Implicit),
+    op(0x01, Ora, IndexedIndirect),
+    op(0x05, Ora, ZeroPage),
+    op(0x06, Asl, ZeroPage),
+    op(0x08, Php, Implicit),
+    op(0x09, Ora, Immediate),
+    op(0x0A, Asl, Accumulator),
+    op(0x0D, Ora, Absolute),
+    op(0x0E, Asl, Absolute),
+    op(0x10, Bpl, Relative),
+    op(0x11, Ora, IndirectIndexed),
+    op(0x15, Ora, ZeroPageX),
+    op(0x16, Asl, ZeroPageX),
+    op(0x18, Clc, Implicit),
+    op(0x19, Ora, AbsoluteY),
+    op(0x1D, Ora, AbsoluteX),
+    op(0x1E, Asl, AbsoluteX),
+    op(0x20, Jsr, Absolute),
+    op(0x21, And, IndexedIndirect),
+    op(0x24, Bit, ZeroPage),
+    op(0x25, And, ZeroPage),
+    op(0x26, Rol, ZeroPage),
+    op(0x28, Plp, Implicit),
+    op(0x29, And, Immediate),
+    op(0x2A, Rol, Accumulator),
+    op(0x2C, Bit, Absolute),
+    op(0x2D, And, Absolute),
+    op(0x2E, Rol, Absolute),
+    op(0x30, Bmi, Relative),
+    op(0x31, And, IndirectIndexed),
+    op(0x35, And, ZeroPageX),
+    op(0x36, Rol, ZeroPageX),
+    op(0x38, Sec, Implicit),
+    op(0x39, And, AbsoluteY),
+    op(0x3D, And, AbsoluteX),
+    op(0x3E, Rol, AbsoluteX),
+    op(0x40, Rti, Implicit),
+    op(0x41, Eor, IndexedIndirect),
+    op(0x45, Eor, ZeroPage),
+    op(0x46, Lsr, ZeroPage),
+    op(0x48, Pha, Implicit),
+    op(0x49, Eor, Immediate),
+    op(0x4A, Lsr, Accumulator),
+    op(0x4C, Jmp, Absolute),
+    op(0x4D, Eor, Absolute),
+    op(0x4E, Lsr, Absolute),
+    op(0x50, Bvc, Relative),
+    op(0x51, Eor, IndirectIndexed),
+    op(0x55, Eor, ZeroPageX),
+    op(0x56, Lsr, ZeroPageX),
+    op(0x58, Cli, Implicit),
+    op(0x59, Eor, AbsoluteY),
+    op(0x5D, Eor, AbsoluteX),
+    op(0x5E, Lsr, AbsoluteX),
+    op(0x60, Rts, Implicit),
+    op(0x61, Adc, IndexedIndirect),
+    op(0x65, Adc, ZeroPage),
+    op(0x66, Ror, ZeroPage),
+    op(0x68, Pla, Implicit),
+    op(0x69, Adc, Immediate),
+    op(0x6A, Ror, Accumulator),
+    op(0x6C, Jmp, Indirect),
+    op(0x6D, Adc, Absolute),
+    op(0x6E, Ror, Absolute),
+    op(0x70, Bvs, Relative),
+    op(0x71, Adc, IndirectIndexed),
+    op(0x75, Adc, ZeroPageX),
+    op(0x76, Ror, ZeroPageX),
+    op(0x78, Sei, Implicit),
+    op(0x79, Adc, AbsoluteY),
+    op(0x7D, Adc, AbsoluteX),
+    op(0x7E, Ror, AbsoluteX),
+    op(0x81, Sta, IndexedIndirect),
+    op(0x84, Sty, ZeroPage),
+    op(0x85, Sta, ZeroPage),
+    op(0x86, Stx, ZeroPage),
+    op(0x88, Dey, Implicit),
+    op(0x8A, Txa, Implicit),
+    op(0x8C, Sty, Absolute),
+    op(0x8D, Sta, Absolute),
+    op(0x8E, Stx, Absolute),
+    op(0x90, Bcc, Relative),
+    op(0x91, Sta, IndirectIndexed),
+    op(0x94, Sty, ZeroPageX),
+    op(0x95, Sta, ZeroPageX),
+    op(0x96, Stx, ZeroPageY),
+    op(0x98, Tya, Implicit),
+    op(0x99, Sta, AbsoluteY),
+    op(0x9A, Txs, Implicit),
+    op(0x9D, Sta, AbsoluteX),
+    op(0xA0, Ldy, Immediate),
+    op(0xA1, Lda, IndexedIndirect),
+    op(0xA2, Ldx, Immediate),
+    op(0xA4, Ldy, ZeroPage),
+    op(0xA5, Lda, ZeroPage),
+    op(0xA6, Ldx, ZeroPage),
+    op(0xA8, Tay, Implicit),
+    op(0xA9, Lda, Immediate),
+    op(0xAA, Tax, Implicit),
+    op(0xAC, Ldy, Absolute),
+    op(0xAD, Lda, Absolute),
+    op(0xAE, Ldx, Absolute),
+    op(0xB0, Bcs, Relative),
+    op(0xB1, Lda, IndirectIndexed),
+    op(0xB4, Ldy, ZeroPageX),
+    op(0xB5, Lda, ZeroPageX),
+    op(0xB6, Ldx, ZeroPageY),
+    op(0xB8, Clv, Implicit),
+    op(0xB9, Lda, AbsoluteY),
+    op(0xBA, Tsx, Implicit),
+    op(0xBC, Ldy, AbsoluteX),
+    op(0xBD, Lda, AbsoluteX),
+    op(0xBE, Ldx, AbsoluteY),
+    op(0xC0, Cpy, Immediate),
+    op(0xC1, Cmp, IndexedIndirect),
+    op(0xC4, Cpy, ZeroPage),
+    op(0xC5, Cmp, ZeroPage),
+    op(0xC6, Dec, ZeroPage),
+    op(0xC8, Iny, Implicit),
+    op(0xC9, Cmp, Immediate),
+    op(0xCA, Dex, Implicit),
+    op(0xCC, Cpy, Absolute),
+    op(0xCD, Cmp, Absolute),
+    op(0xCE, Dec, Absolute),
+    op(0xD0, Bne, Relative),
+    op(0xD1, Cmp, IndirectIndexed),
+    op(0xD5, Cmp, ZeroPageX),
+    op(0xD6, Dec, ZeroPageX),
+    op(0xD8, Cld, Implicit),
+    op(0xD9, Cmp, AbsoluteY),
+    op(0xDD, Cmp, AbsoluteX),
+    op(0xDE, Dec, AbsoluteX),
+    op(0xE0, Cpx, Immediate),
+    op(0xE1, Sbc, IndexedIndirect),
+    op(0xE4, Cpx, ZeroPage),
+    op(0xE5, Sbc, ZeroPage),
+    op(0xE6, Inc, ZeroPage),
+    op(0xE8, Inx, Implicit),
+    op(0xE9, Sbc, Immediate),
+    op(0xEA, Nop, Implicit),
+    op(0xEC, Cpx, Absolute),
+    op(0xED, Sbc, Absolute),
+    op(0xEE, Inc, Absolute),
+    op(0xF0, Beq, Relative),
+    op(0xF1, Sbc, IndirectIndexed),
+    op(0xF5, Sbc, ZeroPageX),
+    op(0xF6, Inc, ZeroPageX),
+    op(0xF8, Sed, Implicit),
+    op(0xF9, Sbc, AbsoluteY),
+    op(0xFD, Sbc, AbsoluteX),
+    op(0xFE, Inc, AbsoluteX),
+];
+
+/// Unofficial opcodes whose result differs between individual CPUs, sorted by `code`
+/// They are only decoded when enabled, see `Config::unstable_opcodes`
+/// See https://www.nesdev.org/wiki/CPU_unofficial_opcodes
+pub const UNSTABLE_OPCODES: [Opcode; 5] = [
+    op(0x8B, Xaa, Immediate),
+    op(0x93, Ahx, IndirectIndexed),
+    op(0x9B, Tas, AbsoluteY),
+    op(0x9F, Ahx, AbsoluteY),
+    op(0xBB, Las, AbsoluteY),
+];
+
+/// Return opcode of instruction @ty with addressing mode @mode, if such encoding exists
+pub fn encode(ty: InstructionType, mode: AddressingMode) -> Option<u8> {
+    OPCODES
+        .iter()
+        .find(|o| o.ty == ty && o.mode == mode)
+        .map(|o| o.code)
+}
+
+/// Return instruction type and addressing mode of @code, None for illegal opcodes
+pub fn decode(code: u8) -> Option<Opcode> {
+    OPCODES
+        .binary_search_by_key(&code, |o| o.code)
+        .ok()
+        .map(|i| OPCODES[i])
+}
+
+/// Return instruction type and addressing mode of unstable opcode @code
+pub fn decode_unstable(code: u8) -> Option<Opcode> {
+    UNSTABLE_OPCODES
+        .binary_search_by_key(&code, |o| o.code)
+        .ok()
+        .map(|i| UNSTABLE_OPCODES[i])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, decode_unstable, encode, OPCODES, UNSTABLE_OPCODES};
+    use crate::instruction::instruction_type::InstructionType;
+    use crate::instruction::operand::AddressingMode;
+
+    #[test]
+    fn sorted_and_unique() {
+        assert!(OPCODES.windows(2).all(|w| w[0].code < w[1].code));
+        assert!(UNSTABLE_OPCODES.windows(2).all(|w| w[0].code < w[1].code));
+    }
+
+    #[test]
+    fn unstable_are_separate() {
+        for o in UNSTABLE_OPCODES.iter() {
+            assert_eq!(decode(o.code), None);
+            assert_eq!(encode(o.ty, o.mode), None);
+            assert_eq!(decode_unstable(o.code), Some(*o));
+        }
+        assert_eq!(decode_unstable(0xA9), None);
+    }
+
+    #[test]
+    fn encode_decode() {
+        assert_eq!(
+            encode(InstructionType::Lda, AddressingMode::Immediate),
+            Some(0xA9)
+        );
+        assert_eq!(
+            encode(InstructionType::Lda, AddressingMode::ZeroPageY),
+            None
+        );
+        let o = decode(0x6C).unwrap();
+        assert_eq!(o.ty, InstructionType::Jmp);
+        assert_eq!(o.mode, AddressingMode::Indirect);
+        assert_eq!(decode(0x02), None);
+    }
+
+    #[test]
+    fn round_trip() {
+        for o in OPCODES.iter() {
+            assert_eq!(encode(o.ty, o.mode), Some(o.code));
+            assert_eq!(decode(o.code), Some(*o));
+        }
+    }
+}
@@ -30,3 +30,34 @@ pub enum Operand {
     /// `address = *(Y + offset)`
     IndirectIndexed(u8),
 }
+
+/// Addressing mode of an instruction, i.e. `Operand` without the value
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum AddressingMode {
+    Implicit,
+    Accumulator,
+    Immediate,
+    ZeroPage,
+    ZeroPageX,
+    ZeroPageY,
+    Relative,
+    Absolute,
+    AbsoluteX,
+    AbsoluteY,
+    Indirect,
+    IndexedIndirect,
+    IndirectIndexed,
+}
+
+impl AddressingMode {
+    /// Number of bytes following the opcode
+    pub fn operand_size(self) -> usize {
+        use AddressingMode::*;
+        match self {
+            Implicit | Accumulator => 0,
+            Immediate | ZeroPage | ZeroPageX | ZeroPageY | Relative => 1,
+            IndexedIndirect | IndirectIndexed => 1,
+            Absolute | AbsoluteX | AbsoluteY | Indirect => 2,
+        }
+    }
+}
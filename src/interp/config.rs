@@ -0,0 +1,54 @@
+use super::state::PowerOnRamPattern;
+
+/// Settings used when creating an interpreter
+#[derive(Clone, Debug, PartialEq)]
+pub struct Config {
+    /// Seed for everything the hardware leaves unspecified
+    /// Two runs with the same config and inputs are identical
+    pub seed: u64,
+    pub ram_pattern: PowerOnRamPattern,
+    /// Record stack anomalies, see `StackCheck`
+    pub stack_check: bool,
+    /// Record reads of ram not written since power-on, see `UninitCheck`
+    pub uninit_check: bool,
+    /// Keep running through errors real hardware survives instead of stopping `step`
+    /// Illegal opcodes run as a one byte NOP, reads outside ram see open bus and
+    /// writes outside ram are dropped. Each such error is kept, see `State::tolerated_errors`
+    pub tolerant: bool,
+    /// Decode unofficial opcodes with unstable results, None treats them as illegal
+    pub unstable_opcodes: Option<UnstableOpcodes>,
+}
+
+/// Behavior of unofficial opcodes whose result depends on the individual CPU
+/// See https://www.nesdev.org/wiki/Visual6502wiki/6502_Opcode_8B_(XAA,_ANE)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct UnstableOpcodes {
+    /// Bits of A which survive XAA, varies with chip and temperature
+    pub xaa_magic: u8,
+    /// Replace the high byte of the AHX and TAS address with the stored value when indexing
+    /// crosses a page, as most CPUs do
+    pub page_cross_glitch: bool,
+}
+
+impl Default for UnstableOpcodes {
+    /// Values matching most NES CPUs and the Tom Harte test suite
+    fn default() -> UnstableOpcodes {
+        UnstableOpcodes {
+            xaa_magic: 0xEE,
+            page_cross_glitch: true,
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            seed: 0,
+            ram_pattern: PowerOnRamPattern::Zeros,
+            stack_check: false,
+            uninit_check: false,
+            tolerant: false,
+            unstable_opcodes: None,
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use super::error::Error;
+use super::state::{State, RAM_SIZE};
+use crate::instruction::instruction::Instruction;
+use crate::instruction::opcode::{decode, decode_unstable};
+use crate::instruction::operand::{AddressingMode, Operand};
+
+/// Build operand of addressing mode @mode from @lo and @hi bytes following the opcode
+fn operand(mode: AddressingMode, lo: u8, hi: u8) -> Operand {
+    let word = (hi as u16) << 8 | lo as u16;
+    match mode {
+        AddressingMode::Implicit => Operand::Implicit,
+        AddressingMode::Accumulator => Operand::Accumulator,
+        AddressingMode::Immediate => Operand::Immediate(lo),
+        AddressingMode::ZeroPage => Operand::ZeroPage(lo),
+        AddressingMode::ZeroPageX => Operand::ZeroPageX(lo),
+        AddressingMode::ZeroPageY => Operand::ZeroPageY(lo),
+        AddressingMode::Relative => Operand::Relative(lo as i8),
+        AddressingMode::Absolute => Operand::Absolute(word),
+        AddressingMode::AbsoluteX => Operand::AbsoluteX(word),
+        AddressingMode::AbsoluteY => Operand::AbsoluteY(word),
+        AddressingMode::Indirect => Operand::Indirect(word),
+        AddressingMode::IndexedIndirect => Operand::IndexedIndirect(lo),
+        AddressingMode::IndirectIndexed => Operand::IndirectIndexed(lo),
+    }
+}
+
+type Read = fn(&State, u16) -> u8;
+
+/// Read byte at @addr for the instruction at @pc using @read
+fn read_checked(state: &State, read: Read, pc: u16, addr: u16) -> Result<u8, Error> {
+    if addr as usize >= RAM_SIZE {
+        return Err(Error::OutOfRam { pc, addr });
+    }
+    Ok(read(state, addr))
+}
+
+/// Decode instruction at @pc, reading its bytes with @read
+/// Bytes outside ram are an error if @checked, otherwise they are whatever @read returns
+fn decode_with(
+    state: &State,
+    read: Read,
+    pc: u16,
+    checked: bool,
+) -> Result<(Instruction, u16), Error> {
+    let read = |addr| {
+        if checked {
+            read_checked(state, read, pc, addr)
+        } else {
+            Ok(read(state, addr))
+        }
+    };
+    let code = read(pc)?;
+    let opcode = decode(code)
+        .or_else(|| state.unstable_opcodes().and(decode_unstable(code)))
+        .ok_or(Error::IllegalOpcode { pc, opcode: code })?;
+
+    let size = opcode.mode.operand_size();
+    let lo = if size > 0 {
+        read(pc.wrapping_add(1))?
+    } else {
+        0
+    };
+    let hi = if size > 1 {
+        read(pc.wrapping_add(2))?
+    } else {
+        0
+    };
+
+    let instruction = Instruction::with_operand(opcode.ty, operand(opcode.mode, lo, hi));
+    Ok((instruction, 1 + size as u16))
+}
+
+/// Decode instruction at @pc without executing it
+/// Memory is only peeked, so this is invisible to the uninit check
+/// Return the instruction and its size in bytes
+pub fn decode_at(state: &State, pc: u16) -> Result<(Instruction, u16), Error> {
+    decode_with(state, State::ram_get, pc, true)
+}
+
+/// Decode instruction at PC and advance PC past it, reading it as the CPU does
+/// PC is left unchanged on error
+pub fn fetch(state: &mut State) -> Result<Instruction, Error> {
+    let (instruction, size) = decode_with(state, State::cpu_read, state.pc, true)?;
+    state.pc = state.pc.wrapping_add(size);
+    Ok(instruction)
+}
+
+/// Same as `fetch`, but bytes outside ram are read as open bus instead of an error
+pub fn fetch_open_bus(state: &mut State) -> Result<Instruction, Error> {
+    let (instruction, size) = decode_with(state, State::cpu_read, state.pc, false)?;
+    state.pc = state.pc.wrapping_add(size);
+    Ok(instruction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fetch;
+    use crate::instruction::instruction_type::InstructionType;
+    use crate::instruction::operand::Operand;
+    use crate::interp::error::Error;
+    use crate::interp::state::State;
+
+    #[test]
+    fn fetch_absolute_x() {
+        let mut state = State::new_undefined();
+        state.load_program(&[0xBD, 0x34, 0x12], 0x0200);
+        let i = fetch(&mut state).unwrap();
+        assert_eq!(i.get_type(), InstructionType::Lda);
+        assert!(matches!(i.get_operand(), Operand::AbsoluteX(0x1234)));
+        assert_eq!(state.pc, 0x0203);
+    }
+
+    #[test]
+    fn fetch_relative() {
+        let mut state = State::new_undefined();
+        state.load_program(&[0xD0, 0xFE], 0x0200);
+        let i = fetch(&mut state).unwrap();
+        assert_eq!(i.get_type(), InstructionType::Bne);
+        assert!(matches!(i.get_operand(), Operand::Relative(-2)));
+        assert_eq!(state.pc, 0x0202);
+    }
+
+    #[test]
+    fn fetch_illegal() {
+        let mut state = State::new_undefined();
+        state.load_program(&[0x02], 0x0200);
+        assert_eq!(
+            fetch(&mut state).err(),
+            Some(Error::IllegalOpcode {
+                pc: 0x0200,
+                opcode: 0x02
+            })
+        );
+        assert_eq!(state.pc, 0x0200);
+    }
+
+    #[test]
+    fn fetch_outside_ram() {
+        let mut state = State::new_undefined();
+        state.load_program(&[0xAD, 0x00], 0x07FE);
+        assert_eq!(
+            fetch(&mut state).err(),
+            Some(Error::OutOfRam {
+                pc: 0x07FE,
+                addr: 0x0800
+            })
+        );
+        state.pc = 0x8000;
+        assert_eq!(
+            fetch(&mut state).err(),
+            Some(Error::OutOfRam {
+                pc: 0x8000,
+                addr: 0x8000
+            })
+        );
+    }
+}
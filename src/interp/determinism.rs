@@ -0,0 +1,146 @@
+use super::state::State;
+
+/// One recorded step of a run: the input applied and the state fingerprint after it
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Checkpoint {
+    pub input: u8,
+    pub fingerprint: u64,
+}
+
+/// First step at which a replay stopped matching the recording
+#[derive(Debug, PartialEq)]
+pub enum Divergence {
+    /// Fingerprints differ at step @frame
+    State {
+        frame: usize,
+        expected: u64,
+        actual: u64,
+    },
+    /// Replay applied a different input than the recording at step @frame
+    Input {
+        frame: usize,
+        expected: u8,
+        actual: u8,
+    },
+    /// Replay has a different number of steps than the recording
+    Length { expected: usize, actual: usize },
+}
+
+/// Records inputs alongside state fingerprints, one entry per emulated frame
+/// (or whatever unit of execution the caller steps by)
+#[derive(Default)]
+pub struct Recorder {
+    checkpoints: Vec<Checkpoint>,
+}
+
+impl Recorder {
+    pub fn new() -> Recorder {
+        Recorder {
+            checkpoints: Vec::new(),
+        }
+    }
+
+    /// Record that @input was applied and the run reached @state
+    pub fn record(&mut self, input: u8, state: &State) {
+        self.checkpoints.push(Checkpoint {
+            input,
+            fingerprint: state.fingerprint(),
+        });
+    }
+
+    pub fn checkpoints(&self) -> &[Checkpoint] {
+        &self.checkpoints
+    }
+}
+
+/// Compare a replay against a recording and report the first divergence
+pub fn verify(recording: &[Checkpoint], replay: &[Checkpoint]) -> Result<(), Divergence> {
+    for (frame, (expected, actual)) in recording.iter().zip(replay.iter()).enumerate() {
+        if expected.input != actual.input {
+            return Err(Divergence::Input {
+                frame,
+                expected: expected.input,
+                actual: actual.input,
+            });
+        }
+        if expected.fingerprint != actual.fingerprint {
+            return Err(Divergence::State {
+                frame,
+                expected: expected.fingerprint,
+                actual: actual.fingerprint,
+            });
+        }
+    }
+
+    if recording.len() != replay.len() {
+        return Err(Divergence::Length {
+            expected: recording.len(),
+            actual: replay.len(),
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{verify, Divergence, Recorder};
+    use crate::interp::state::State;
+
+    fn run(inputs: &[u8], glitch_at: Option<usize>) -> Recorder {
+        let mut st = State::new_undefined();
+        let mut rec = Recorder::new();
+        for (i, input) in inputs.iter().enumerate() {
+            st.accumulator = st.accumulator.wrapping_add(*input);
+            if glitch_at == Some(i) {
+                st.x = st.x.wrapping_add(1);
+            }
+            rec.record(*input, &st);
+        }
+        rec
+    }
+
+    #[test]
+    fn identical_replay_verifies() {
+        let a = run(&[1, 2, 3], None);
+        let b = run(&[1, 2, 3], None);
+        assert_eq!(verify(a.checkpoints(), b.checkpoints()), Ok(()));
+    }
+
+    #[test]
+    fn state_divergence_is_reported() {
+        let a = run(&[1, 2, 3], None);
+        let b = run(&[1, 2, 3], Some(1));
+        match verify(a.checkpoints(), b.checkpoints()) {
+            Err(Divergence::State { frame, .. }) => assert_eq!(frame, 1),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn input_divergence_is_reported() {
+        let a = run(&[1, 2, 3], None);
+        let b = run(&[1, 5, 3], None);
+        assert_eq!(
+            verify(a.checkpoints(), b.checkpoints()),
+            Err(Divergence::Input {
+                frame: 1,
+                expected: 2,
+                actual: 5
+            })
+        );
+    }
+
+    #[test]
+    fn length_divergence_is_reported() {
+        let a = run(&[1, 2, 3], None);
+        let b = run(&[1, 2], None);
+        assert_eq!(
+            verify(a.checkpoints(), b.checkpoints()),
+            Err(Divergence::Length {
+                expected: 3,
+                actual: 2
+            })
+        );
+    }
+}
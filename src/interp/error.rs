@@ -0,0 +1,10 @@
+/// Reason why the interpreter could not execute an instruction
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Error {
+    /// @opcode found at @pc is not an official instruction
+    IllegalOpcode { pc: u16, opcode: u8 },
+    /// Instruction at @pc accesses @addr, which is outside of ram
+    OutOfRam { pc: u16, addr: u16 },
+    /// BRK was not reached within @steps instructions
+    StepLimit { steps: usize },
+}
@@ -1,34 +1,61 @@
+use super::alu;
 use super::alu::is_negative;
+use super::decoder::{decode_at, fetch, fetch_open_bus};
+use super::error::Error;
+use super::flags::PushSource;
 use super::operand_decoder;
 use super::operand_decoder::{get_pointer, get_u8, set_u8};
+use crate::instruction::instruction::Instruction;
+use crate::instruction::instruction_type::InstructionType;
 use crate::instruction::operand::Operand;
-use crate::interp::state::State;
+use crate::interp::state::{State, RAM_SIZE};
+use crate::trace::logger::{Level, Target};
+
+/// Address of the IRQ/BRK interrupt vector
+const IRQ_VECTOR: u16 = 0xFFFE;
+
+/// Return whether branch instruction @ty would be taken in @state, None if @ty is not a branch
+fn branch_taken(ty: InstructionType, state: &State) -> Option<bool> {
+    use InstructionType::*;
+    let taken = match ty {
+        Bcc => !state.get_carry(),
+        Bcs => state.get_carry(),
+        Beq => state.get_zero(),
+        Bne => !state.get_zero(),
+        Bmi => state.get_negative(),
+        Bpl => !state.get_negative(),
+        Bvc => !state.get_overflow(),
+        Bvs => state.get_overflow(),
+        _ => return None,
+    };
+    Some(taken)
+}
 
-/// Create a function @name which checks the flag @flag.
+/// Create a function @name which branches if @ty would be taken.
 macro_rules! branch_inst {
-    ($name:ident, $pred:expr) => {
+    ($name:ident, $ty:ident) => {
         fn $name(state: &mut State, op: &Operand) {
             let dest = match op {
                 Operand::Relative(rel) => state.pc.wrapping_add((*rel as i16) as u16),
                 _ => unimplemented!("{}: operand is not Relative(i8)", stringify!($name)),
             };
 
-            if $pred(&state) {
+            if branch_taken(InstructionType::$ty, state) == Some(true) {
                 state.pc = dest;
             }
         }
     };
 }
 
-branch_inst!(bcc, |s: &State| !s.get_carry());
-branch_inst!(bcs, |s: &State| s.get_carry());
-branch_inst!(beq, |s: &State| s.get_zero());
-branch_inst!(bne, |s: &State| !s.get_zero());
-branch_inst!(bmi, |s: &State| s.get_negative());
-branch_inst!(bpl, |s: &State| !s.get_negative());
+branch_inst!(bcc, Bcc);
+branch_inst!(bcs, Bcs);
+branch_inst!(beq, Beq);
+branch_inst!(bne, Bne);
+branch_inst!(bmi, Bmi);
+branch_inst!(bpl, Bpl);
 
-branch_inst!(bvc, |s: &State| !s.get_overflow());
-branch_inst!(bvs, |s: &State| s.get_overflow());
+branch_inst!(bvc, Bvc);
+branch_inst!(bvs, Bvs);
 
 fn bit(state: &mut State, op: &Operand) {
     let a = state.accumulator;
@@ -39,20 +66,32 @@ fn bit(state: &mut State, op: &Operand) {
     state.set_overflow(r & (1 << 6) > 0);
 }
 
-// TODO test this after MMU is done
-// since interrupt vector at 0xFFFE is outside ram, it doesn't work without MMU
+/// Push @return_addr and flags the way BRK, IRQ and NMI do and disable interrupts
+/// B only exists in the pushed copy of the flags, it is set for @source of `Instruction`
+fn enter_interrupt(state: &mut State, return_addr: u16, source: PushSource) {
+    state.log(
+        Target::Cpu,
+        Level::Debug,
+        format_args!(
+            "interrupt from {:?}, returning to {:04X}",
+            source, return_addr
+        ),
+    );
+    state.push_u16(return_addr);
+    state.push_flags(source);
+    state.set_interrupt(true);
+}
+
+// since interrupt vector at 0xFFFE is outside ram, step() refuses to run BRK until there is an MMU
 fn brk(state: &mut State, op: &Operand) {
     match op {
         Operand::Implicit => {}
         _ => panic!("brk: there must be no operand!"),
     };
 
-    // push lower bits then higher bits
-    // TODO the stack order
-    state.push_pc();
-    state.stack_push(state.psw);
-    state.set_break(true);
-    state.pc = get_pointer(&Operand::Indirect(0xFFFE), &state).unwrap();
+    // BRK is followed by a padding byte, the pushed address skips it
+    enter_interrupt(state, state.pc.wrapping_add(1), PushSource::Instruction);
+    state.pc = state.cpu_read_u16(IRQ_VECTOR, IRQ_VECTOR + 1);
 }
 
 fn rti(state: &mut State, op: &Operand) {
@@ -61,9 +100,8 @@ fn rti(state: &mut State, op: &Operand) {
         _ => panic!("brk: there must be no operand!"),
     };
 
-    // TODO the stack order
     // pop psw
-    state.psw = state.stack_pop();
+    state.pull_flags();
     // pop pc
     state.pop_pc();
 }
@@ -88,7 +126,8 @@ macro_rules! flag {
 
 flag!(clc, sec, set_carry);
 flag!(cli, sei, set_interrupt);
-flag!(clv, set_interrupt);
+flag!(clv, set_overflow);
+flag!(cld, sed, set_decimal);
 
 fn jmp(state: &mut State, op: &Operand) {
     let d = get_pointer(&op, &state).expect("jmp: operand is required");
@@ -97,7 +136,12 @@ fn jmp(state: &mut State, op: &Operand) {
 
 fn jsr(state: &mut State, op: &Operand) {
     let d = get_pointer(&op, &state).expect("jsr: operand is required");
-    state.push_pc();
+    // the pushed address is the last byte of JSR, RTS adds 1
+    let (sp, ret) = (state.sp, state.pc.wrapping_sub(1));
+    state.push_u16(ret);
+    if let Some(check) = state.stack_check_mut() {
+        check.on_call(sp, ret);
+    }
     state.pc = d;
 }
 
@@ -141,18 +185,66 @@ macro_rules! transfer {
 transfer!(tax, accumulator, x);
 transfer!(tay, accumulator, y);
 transfer!(tsx, sp, x);
-transfer!(txs, x, sp);
 transfer!(txa, x, accumulator);
 transfer!(tya, y, accumulator);
 
+/// Unlike other transfers, TXS leaves flags untouched
+fn txs(state: &mut State, _op: &Operand) {
+    state.sp = state.x;
+}
+
 fn nop(_state: &mut State, _op: &Operand) {}
 
+fn xaa(state: &mut State, op: &Operand) {
+    let magic = state.unstable_opcodes().unwrap_or_default().xaa_magic;
+    let value = get_u8(op, state).expect("xaa: operand is required");
+    let r = (state.accumulator | magic) & state.x & value;
+    state.accumulator = r;
+    state.set_zero(r == 0);
+    state.set_negative(is_negative(r));
+}
+
+fn las(state: &mut State, op: &Operand) {
+    let r = get_u8(op, state).expect("las: operand is required") & state.sp;
+    state.accumulator = r;
+    state.x = r;
+    state.sp = r;
+    state.set_zero(r == 0);
+    state.set_negative(is_negative(r));
+}
+
+/// Store @value AND (H + 1) for AHX and TAS, H being the high byte of the address before
+/// adding Y. With the page cross glitch, a crossing store replaces H with the stored value.
+// the glitched address may leave ram even though the indexed one passed `check_access`,
+// `cpu_write` drops such a store
+fn store_and_high(state: &mut State, op: &Operand, value: u8) {
+    let addr = get_pointer(op, state).expect("ahx: operand must be a pointer");
+    let base = addr.wrapping_sub(state.y as u16);
+    let stored = value & ((base >> 8) as u8).wrapping_add(1);
+    let glitch = matches!(state.unstable_opcodes(), Some(u) if u.page_cross_glitch);
+    let addr = if glitch && (base ^ addr) & 0xFF00 != 0 {
+        (stored as u16) << 8 | (addr & 0x00FF)
+    } else {
+        addr
+    };
+    state.cpu_write(addr, stored);
+}
+
+fn ahx(state: &mut State, op: &Operand) {
+    store_and_high(state, op, state.accumulator & state.x);
+}
+
+fn tas(state: &mut State, op: &Operand) {
+    state.sp = state.accumulator & state.x;
+    store_and_high(state, op, state.sp);
+}
+
 fn pha(state: &mut State, _op: &Operand) {
     state.stack_push(state.accumulator);
 }
 
 fn php(state: &mut State, _op: &Operand) {
-    state.stack_push(state.psw);
+    state.push_flags(PushSource::Instruction);
 }
 
 fn pla(state: &mut State, _op: &Operand) {
@@ -162,12 +254,199 @@ fn pla(state: &mut State, _op: &Operand) {
 }
 
 fn plp(state: &mut State, _op: &Operand) {
-    state.psw = state.stack_pop();
+    state.pull_flags();
 }
 
 fn rts(state: &mut State, op: &Operand) {
     // complement of jsr
-    state.pop_pc();
+    let sp = state.sp;
+    let addr = state.pop_u16();
+    if let Some(check) = state.stack_check_mut() {
+        check.on_return(sp.wrapping_add(2), addr);
+    }
+    state.pc = addr.wrapping_add(1);
+}
+
+/// Execute @instruction, PC must already point to the next instruction
+/// Memory accesses are not checked here, `step` is the entry point which does that
+fn execute(state: &mut State, instruction: &Instruction) {
+    use InstructionType::*;
+    let f = match instruction.get_type() {
+        Adc => alu::adc,
+        And => alu::and,
+        Asl => alu::asl,
+        Bit => bit,
+        Bpl => bpl,
+        Bmi => bmi,
+        Bvc => bvc,
+        Bvs => bvs,
+        Bcc => bcc,
+        Bcs => bcs,
+        Bne => bne,
+        Beq => beq,
+        Brk => brk,
+        Cmp => alu::cmp,
+        Cpx => alu::cpx,
+        Cpy => alu::cpy,
+        Dec => alu::dec,
+        Eor => alu::eor,
+        Clc => clc,
+        Sec => sec,
+        Cli => cli,
+        Sei => sei,
+        Clv => clv,
+        Cld => cld,
+        Sed => sed,
+        Inc => alu::inc,
+        Jmp => jmp,
+        Jsr => jsr,
+        Lda => lda,
+        Ldx => ldx,
+        Ldy => ldy,
+        Lsr => alu::lsr,
+        Nop => nop,
+        Ora => alu::ora,
+        Tax => tax,
+        Txa => txa,
+        Dex => alu::dex,
+        Inx => alu::inx,
+        Tay => tay,
+        Tya => tya,
+        Dey => alu::dey,
+        Iny => alu::iny,
+        Rol => alu::rol,
+        Ror => alu::ror,
+        Rti => rti,
+        Rts => rts,
+        Sbc => alu::sbc,
+        Sta => sta,
+        Txs => txs,
+        Tsx => tsx,
+        Pha => pha,
+        Pla => pla,
+        Php => php,
+        Plp => plp,
+        Stx => stx,
+        Sty => sty,
+        Xaa => xaa,
+        Ahx => ahx,
+        Tas => tas,
+        Las => las,
+    };
+    f(state, instruction.get_operand());
+}
+
+/// Return an error if @instruction at @pc would access memory outside of ram
+fn check_access(state: &State, instruction: &Instruction, pc: u16) -> Result<(), Error> {
+    use InstructionType::*;
+    let op = instruction.get_operand();
+    let addr = match (instruction.get_type(), op) {
+        // jump targets are checked when the next instruction is fetched
+        (Jmp, Operand::Absolute(_)) | (Jsr, _) | (_, Operand::Relative(_)) => return Ok(()),
+        (Brk, _) => IRQ_VECTOR,
+        // both pointer bytes are in the same page, ram ends at a page boundary
+        (_, Operand::Indirect(ptr)) => *ptr,
+        _ => match get_pointer(op, state) {
+            Some(addr) => addr,
+            None => return Ok(()),
+        },
+    };
+    if addr as usize >= RAM_SIZE {
+        return Err(Error::OutOfRam { pc, addr });
+    }
+    Ok(())
+}
+
+/// Fetch the instruction at PC in tolerant mode, recording every error run through
+/// Return None for an illegal opcode, which is skipped as a one byte NOP
+fn fetch_tolerant(state: &mut State) -> Option<Instruction> {
+    let pc = state.pc;
+    let fetched = match fetch(state) {
+        Err(e @ Error::OutOfRam { .. }) => {
+            state.tolerate(e);
+            fetch_open_bus(state)
+        }
+        fetched => fetched,
+    };
+    match fetched {
+        Ok(instruction) => Some(instruction),
+        Err(e) => {
+            state.tolerate(e);
+            state.pc = pc.wrapping_add(1);
+            None
+        }
+    }
+}
+
+/// Log @instruction at @pc as a line of its bytes, mnemonic and registers before it runs
+fn trace_instruction(state: &mut State, instruction: &Instruction, pc: u16) {
+    if !state.log_enabled(Target::Cpu, Level::Trace) {
+        return;
+    }
+    let size = state.pc.wrapping_sub(pc);
+    let bytes: Vec<String> = (0..size)
+        .map(|i| format!("{:02X}", state.cpu_peek(pc.wrapping_add(i))))
+        .collect();
+    let line = format!(
+        "{:04X}  {:<8}  {:<4} A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X}",
+        pc,
+        bytes.join(" "),
+        instruction.get_type().mnemonic(),
+        state.accumulator,
+        state.x,
+        state.y,
+        state.psw,
+        state.sp
+    );
+    state.log(Target::Cpu, Level::Trace, format_args!("{}", line));
+}
+
+/// Fetch, decode and execute one instruction at PC
+/// State is left unchanged on error
+/// In tolerant mode this never fails, see `Config::tolerant`
+pub fn step(state: &mut State) -> Result<(), Error> {
+    let pc = state.pc;
+    if let Some(check) = state.stack_check_mut() {
+        check.set_pc(pc);
+    }
+    if let Some(check) = state.uninit_check_mut() {
+        check.set_pc(pc);
+    }
+    if state.tolerant() {
+        if let Some(instruction) = fetch_tolerant(state) {
+            if let Err(e) = check_access(state, &instruction, pc) {
+                state.tolerate(e);
+            }
+            trace_instruction(state, &instruction, pc);
+            execute(state, &instruction);
+        }
+        return Ok(());
+    }
+    let instruction = fetch(state)?;
+    if let Err(e) = check_access(state, &instruction, pc) {
+        state.pc = pc;
+        return Err(e);
+    }
+    trace_instruction(state, &instruction, pc);
+    execute(state, &instruction);
+    Ok(())
+}
+
+/// Return true if the instruction at PC jumps or branches to itself
+/// Such a loop makes no progress until an interrupt arrives, e.g. games waiting for NMI.
+pub fn is_idle_loop(state: &State) -> bool {
+    let instruction = match decode_at(state, state.pc) {
+        Ok((instruction, _)) => instruction,
+        Err(_) => return false,
+    };
+    match instruction.get_operand() {
+        Operand::Absolute(addr) if instruction.get_type() == InstructionType::Jmp => {
+            *addr == state.pc
+        }
+        // offset is relative to the end of the 2-byte branch
+        Operand::Relative(-2) => branch_taken(instruction.get_type(), state) == Some(true),
+        _ => false,
+    }
 }
 
 #[cfg(test)]
@@ -265,25 +544,452 @@ mod tests {
         }
     }
 
-    // mod brk {
-    //     use crate::instruction::operand::Operand;
-    //     use crate::interp::execution::brk;
-    //     use crate::interp::state::State;
-
-    //     #[test]
-    //     fn brk_test() {
-    //         let mut state = State::new_undefined();
-    //         state.set_break(false);
-    //         state.set_overflow(true);
-    //         state.set_negative(true);
-    //         state.set_carry(true);
-
-    //         let op = Operand::Implicit;
-    //         brk(&mut state, &op);
-
-    //         assert!(state.get_break());
-    //     }
-    // }
+    mod idle {
+        use crate::asm::assembler::assemble;
+        use crate::interp::execution::step;
+        use crate::interp::state::State;
+
+        fn load(source: &str) -> State {
+            let mut state = State::new_undefined();
+            state.load_program(&assemble(source).unwrap(), 0x0600);
+            state
+        }
+
+        #[test]
+        fn jmp_self() {
+            let state = load(".org $0600\nloop: jmp loop");
+            assert!(state.is_idle_loop());
+        }
+
+        #[test]
+        fn branch_self() {
+            let mut state = load(".org $0600\nwait: beq wait");
+            state.set_zero(true);
+            assert!(state.is_idle_loop());
+            state.set_zero(false);
+            assert!(!state.is_idle_loop());
+        }
+
+        #[test]
+        fn not_idle() {
+            let mut state = load(
+                "
+                .org $0600
+                        ldx #3
+                count:  dex
+                        bne count
+                idle:   jmp idle
+                ",
+            );
+            let mut steps = 0;
+            while !state.is_idle_loop() {
+                step(&mut state).unwrap();
+                steps += 1;
+            }
+            assert_eq!(steps, 1 + 3 * 2);
+            assert_eq!(state.x, 0);
+        }
+
+        #[test]
+        fn illegal_opcode() {
+            let mut state = State::new_undefined();
+            state.load_program(&[0x02], 0x0600);
+            assert!(!state.is_idle_loop());
+        }
+    }
+
+    mod step {
+        use crate::asm::assembler::assemble;
+        use crate::interp::error::Error;
+        use crate::interp::execution::step;
+        use crate::interp::state::State;
+
+        fn run(source: &str) -> (State, Result<(), Error>) {
+            let mut state = State::new_undefined();
+            state.sp = 0xFF;
+            state.load_program(&assemble(source).unwrap(), 0x0600);
+            let result = (0..100).try_for_each(|_| step(&mut state));
+            (state, result)
+        }
+
+        #[test]
+        fn jsr_rts_stack_layout() {
+            let mut state = State::new_undefined();
+            state.sp = 0xFF;
+            state.load_program(&assemble(".org $0600\njsr $0700").unwrap(), 0x0600);
+            step(&mut state).unwrap();
+            assert_eq!(state.pc, 0x0700);
+            // high byte first, address of the last byte of JSR
+            assert_eq!(state.ram_get(0x01FF), 0x06);
+            assert_eq!(state.ram_get(0x01FE), 0x02);
+
+            state.load_program(&[0x60], 0x0700);
+            step(&mut state).unwrap();
+            assert_eq!(state.pc, 0x0603);
+            assert_eq!(state.sp, 0xFF);
+        }
+
+        #[test]
+        fn txs_keeps_flags() {
+            let (state, _) = run(".org $0600\nldx #$80\nlda #0\ntxs\nbrk");
+            assert_eq!(state.sp, 0x80);
+            assert!(state.get_zero());
+            assert!(!state.get_negative());
+        }
+
+        #[test]
+        fn data_outside_ram() {
+            let (state, result) = run(".org $0600\nlda #1\nsta $8000");
+            assert_eq!(
+                result,
+                Err(Error::OutOfRam {
+                    pc: 0x0602,
+                    addr: 0x8000
+                })
+            );
+            assert_eq!(state.pc, 0x0602);
+        }
+
+        #[test]
+        fn indirect_outside_ram() {
+            let (state, result) = run(".org $0600\njmp ($2000)");
+            assert_eq!(
+                result,
+                Err(Error::OutOfRam {
+                    pc: 0x0600,
+                    addr: 0x2000
+                })
+            );
+            assert_eq!(state.pc, 0x0600);
+        }
+
+        #[test]
+        fn brk_vector_outside_ram() {
+            let (_, result) = run(".org $0600\nbrk");
+            assert_eq!(
+                result,
+                Err(Error::OutOfRam {
+                    pc: 0x0600,
+                    addr: 0xFFFE
+                })
+            );
+        }
+
+        fn tolerant(program: &[u8], steps: usize) -> State {
+            let mut state = State::new_undefined();
+            state.sp = 0xFF;
+            state.set_tolerant(true);
+            state.load_program(program, 0x0600);
+            for _ in 0..steps {
+                step(&mut state).unwrap();
+            }
+            state
+        }
+
+        #[test]
+        fn tolerant_illegal_opcode_is_nop() {
+            // 0x02 jams a real 6502, lda #5 follows
+            let state = tolerant(&[0x02, 0xA9, 0x05], 2);
+            assert_eq!(state.accumulator, 5);
+            assert_eq!(state.pc, 0x0603);
+            assert_eq!(
+                state.tolerated_errors(),
+                &[Error::IllegalOpcode {
+                    pc: 0x0600,
+                    opcode: 0x02
+                }]
+            );
+        }
+
+        #[test]
+        fn tolerant_open_bus() {
+            let program = assemble(".org $0600\nlda #1\nsta $8000\nlda $8000").unwrap();
+            let mut state = tolerant(&program, 3);
+            assert_eq!(state.accumulator, 0x80);
+            assert_eq!(state.pc, 0x0608);
+            assert_eq!(
+                state.take_tolerated_errors(),
+                vec![
+                    Error::OutOfRam {
+                        pc: 0x0602,
+                        addr: 0x8000
+                    },
+                    Error::OutOfRam {
+                        pc: 0x0605,
+                        addr: 0x8000
+                    },
+                ]
+            );
+            assert!(state.tolerated_errors().is_empty());
+        }
+
+        #[test]
+        fn tolerant_fetch_outside_ram() {
+            let mut state = tolerant(&[0x4C, 0x00, 0x80], 2);
+            // the opcode byte reads as open bus 0x80, which is not an official opcode
+            assert_eq!(state.pc, 0x8001);
+            assert_eq!(
+                state.take_tolerated_errors(),
+                vec![
+                    Error::OutOfRam {
+                        pc: 0x8000,
+                        addr: 0x8000
+                    },
+                    Error::IllegalOpcode {
+                        pc: 0x8000,
+                        opcode: 0x80
+                    },
+                ]
+            );
+        }
+    }
+
+    #[cfg(feature = "trace")]
+    mod trace {
+        use crate::asm::assembler::assemble;
+        use crate::interp::execution::step;
+        use crate::interp::state::State;
+        use crate::trace::logger::{Level, Target, WriteLogger};
+        use std::io::{self, Write};
+        use std::sync::{Arc, Mutex};
+
+        /// Buffer kept by the test after the logger is moved into the state
+        #[derive(Clone, Default)]
+        struct Shared(Arc<Mutex<Vec<u8>>>);
+
+        impl Write for Shared {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0.lock().unwrap().write(buf)
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        #[test]
+        fn instructions_and_tolerated_errors() {
+            let out = Shared::default();
+            let logger = WriteLogger::new(out.clone())
+                .with_level(Target::Cpu, Some(Level::Trace))
+                .with_level(Target::Interp, Some(Level::Warn));
+            let mut state = State::new_undefined();
+            state.set_tolerant(true);
+            state.set_logger(Some(Box::new(logger)));
+            state.load_program(&assemble(".org $0600\nlda #5\nsta $8000").unwrap(), 0x0600);
+            step(&mut state).unwrap();
+            step(&mut state).unwrap();
+
+            let text = String::from_utf8(out.0.lock().unwrap().clone()).unwrap();
+            let lines: Vec<&str> = text.lines().collect();
+            assert_eq!(
+                lines,
+                vec![
+                    "[cpu TRACE] 0600  A9 05     LDA  A:00 X:00 Y:00 P:20 SP:00",
+                    "[interp WARN] tolerated OutOfRam { pc: 1538, addr: 32768 }",
+                    "[cpu TRACE] 0602  8D 00 80  STA  A:05 X:00 Y:00 P:20 SP:00",
+                ]
+            );
+        }
+    }
+
+    mod unstable {
+        use crate::interp::config::UnstableOpcodes;
+        use crate::interp::error::Error;
+        use crate::interp::execution::step;
+        use crate::interp::state::State;
+
+        fn run(program: &[u8], unstable: Option<UnstableOpcodes>) -> (State, Result<(), Error>) {
+            let mut state = State::new_undefined();
+            state.sp = 0xFF;
+            state.set_unstable_opcodes(unstable);
+            state.load_program(program, 0x0600);
+            // programs end with the zero byte after them
+            let mut result = Ok(());
+            while result.is_ok() && state.ram_get(state.pc) != 0x00 {
+                result = step(&mut state);
+            }
+            (state, result)
+        }
+
+        #[test]
+        fn disabled_by_default() {
+            let (_, result) = run(&[0x8B, 0x3C], None);
+            assert_eq!(
+                result,
+                Err(Error::IllegalOpcode {
+                    pc: 0x0600,
+                    opcode: 0x8B
+                })
+            );
+        }
+
+        #[test]
+        fn xaa() {
+            // lda #$FF, ldx #$0F, xaa #$3C
+            let program = [0xA9, 0xFF, 0xA2, 0x0F, 0x8B, 0x3C];
+            let (state, _) = run(&program, Some(UnstableOpcodes::default()));
+            assert_eq!(state.accumulator, 0x0C);
+            let magic = UnstableOpcodes {
+                xaa_magic: 0x00,
+                ..UnstableOpcodes::default()
+            };
+            // lda #$01, ldx #$FF, xaa #$FF
+            let (state, _) = run(&[0xA9, 0x01, 0xA2, 0xFF, 0x8B, 0xFF], Some(magic));
+            assert_eq!(state.accumulator, 0x01);
+            assert!(!state.get_zero());
+        }
+
+        #[test]
+        fn las() {
+            // ldy #$10, las $0200,y
+            let mut state = State::new_undefined();
+            state.sp = 0xF0;
+            state.set_unstable_opcodes(Some(UnstableOpcodes::default()));
+            state.load_program(&[0xA0, 0x10, 0xBB, 0x00, 0x02], 0x0600);
+            state.ram_set(0x0210, 0xBC);
+            step(&mut state).unwrap();
+            step(&mut state).unwrap();
+            assert_eq!(state.accumulator, 0xB0);
+            assert_eq!(state.x, 0xB0);
+            assert_eq!(state.sp, 0xB0);
+            assert!(state.get_negative());
+        }
+
+        #[test]
+        fn tas() {
+            // lda #$FF, ldx #$F3, ldy #$10, tas $0200,y
+            let program = [0xA9, 0xFF, 0xA2, 0xF3, 0xA0, 0x10, 0x9B, 0x00, 0x02];
+            let (state, _) = run(&program, Some(UnstableOpcodes::default()));
+            assert_eq!(state.sp, 0xF3);
+            assert_eq!(state.ram_get(0x0210), 0x03);
+        }
+
+        #[test]
+        fn ahx_indirect() {
+            // lda #$FF, ldx #$FF, ldy #$05, ahx ($10),y with $10 pointing to $0200
+            let program = [0xA9, 0xFF, 0xA2, 0xFF, 0xA0, 0x05, 0x93, 0x10];
+            let mut state = State::new_undefined();
+            state.set_unstable_opcodes(Some(UnstableOpcodes::default()));
+            state.load_program(&program, 0x0600);
+            state.ram_set(0x10, 0x00);
+            state.ram_set(0x11, 0x02);
+            (0..4).for_each(|_| step(&mut state).unwrap());
+            assert_eq!(state.ram_get(0x0205), 0x03);
+        }
+
+        #[test]
+        fn ahx_page_cross() {
+            // lda #$05, ldx #$FF, ldy #$10, ahx $02F8,y
+            let program = [0xA9, 0x05, 0xA2, 0xFF, 0xA0, 0x10, 0x9F, 0xF8, 0x02];
+            let (state, _) = run(&program, Some(UnstableOpcodes::default()));
+            // 0x05 & 0x03 also replaces the high byte of 0x0308
+            assert_eq!(state.ram_get(0x0108), 0x01);
+            assert_eq!(state.ram_get(0x0308), 0x00);
+
+            let stable = UnstableOpcodes {
+                page_cross_glitch: false,
+                ..UnstableOpcodes::default()
+            };
+            let (state, _) = run(&program, Some(stable));
+            assert_eq!(state.ram_get(0x0108), 0x00);
+            assert_eq!(state.ram_get(0x0308), 0x01);
+        }
+    }
+
+    mod flags {
+        use crate::instruction::operand::Operand;
+        use crate::interp::execution::{cld, clv, sed};
+        use crate::interp::state::State;
+
+        #[test]
+        fn test_clv() {
+            let mut state = State::new_undefined();
+            state.set_overflow(true);
+            state.set_interrupt(true);
+            clv(&mut state, &Operand::Implicit);
+            assert!(!state.get_overflow());
+            assert!(state.get_interrupt());
+        }
+
+        #[test]
+        fn test_cld_sed() {
+            let mut state = State::new_undefined();
+            sed(&mut state, &Operand::Implicit);
+            assert!(state.get_decimal());
+            cld(&mut state, &Operand::Implicit);
+            assert!(!state.get_decimal());
+        }
+    }
+
+    mod php_plp {
+        use crate::instruction::operand::Operand;
+        use crate::interp::execution::{php, plp};
+        use crate::interp::state::State;
+
+        #[test]
+        fn test_php_sets_b() {
+            let mut state = State::new_undefined();
+            state.set_break(false);
+            state.set_carry(true);
+            php(&mut state, &Operand::Implicit);
+
+            assert_eq!(state.stack_pop(), 0b0011_0001);
+            assert!(!state.get_break());
+        }
+
+        #[test]
+        fn test_plp_ignores_b() {
+            let mut state = State::new_undefined();
+            state.set_break(false);
+            state.stack_push(0xFF);
+            plp(&mut state, &Operand::Implicit);
+
+            assert!(!state.get_break());
+            assert!(state.get_carry());
+            assert!(state.get_negative());
+        }
+    }
+
+    mod interrupt {
+        use crate::interp::execution::enter_interrupt;
+        use crate::interp::flags::{PushSource, PSW_BREAK_BIT, PSW_INTERRUPT_BIT};
+        use crate::interp::state::State;
+
+        fn enter(source: PushSource) -> State {
+            let mut state = State::new_undefined();
+            state.sp = 0xFF;
+            state.set_break(false);
+            state.set_interrupt(false);
+            state.set_carry(true);
+            enter_interrupt(&mut state, 0x1234, source);
+            state
+        }
+
+        #[test]
+        fn brk_frame() {
+            let state = enter(PushSource::Instruction);
+            assert_eq!(state.ram_get(0x1FF), 0x12);
+            assert_eq!(state.ram_get(0x1FE), 0x34);
+            let pushed = state.ram_get(0x1FD);
+            assert!(pushed & PSW_BREAK_BIT > 0);
+            assert!(pushed & PSW_INTERRUPT_BIT == 0);
+            assert_eq!(state.sp, 0xFC);
+
+            // B lives only on the stack, I is set on the live register
+            assert!(!state.get_break());
+            assert!(state.get_interrupt());
+            assert!(state.get_carry());
+        }
+
+        #[test]
+        fn irq_frame() {
+            let state = enter(PushSource::Interrupt);
+            assert!(state.ram_get(0x1FD) & PSW_BREAK_BIT == 0);
+            assert!(!state.get_break());
+            assert!(state.get_interrupt());
+        }
+    }
 }
 
 pub use super::alu::adc;
@@ -0,0 +1,150 @@
+pub const PSW_CARRY_BIT: u8 = 1 << 0;
+pub const PSW_ZERO_BIT: u8 = 1 << 1;
+pub const PSW_INTERRUPT_BIT: u8 = 1 << 2;
+pub const PSW_DECIMAL_BIT: u8 = 1 << 3;
+pub const PSW_BREAK_BIT: u8 = 1 << 4;
+pub const PSW_ONE_BIT: u8 = 1 << 5;
+pub const PSW_OVERFLOW_BIT: u8 = 1 << 6;
+pub const PSW_NEGATIVE_BIT: u8 = 1 << 7;
+
+/// Status word split into named flags
+/// Bit 5 of the status word is always 1, so it has no field here
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Flags {
+    pub negative: bool,
+    pub overflow: bool,
+    pub brk: bool,
+    /// for Ricoh CPU in the NES, this has no effect on arithmetic
+    pub decimal: bool,
+    pub interrupt: bool,
+    pub zero: bool,
+    pub carry: bool,
+}
+
+/// What pushed the status word to the stack
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PushSource {
+    /// PHP and BRK push the status word with B set
+    Instruction,
+    /// IRQ and NMI push the status word with B clear
+    Interrupt,
+}
+
+impl Flags {
+    /// Return the byte to push to the stack when @source saves the status word
+    /// Bit 5 is always set, bit 4 (B) depends only on @source
+    pub fn to_pushed(self, source: PushSource) -> u8 {
+        Flags {
+            brk: source == PushSource::Instruction,
+            ..self
+        }
+        .into()
+    }
+
+    /// Return flags after PLP or RTI pulled @pulled from the stack
+    /// Bits 4 and 5 of @pulled are ignored, B keeps its current value
+    pub fn restore_pulled(self, pulled: u8) -> Flags {
+        Flags {
+            brk: self.brk,
+            ..Flags::from(pulled)
+        }
+    }
+}
+
+impl From<u8> for Flags {
+    fn from(psw: u8) -> Flags {
+        Flags {
+            negative: psw & PSW_NEGATIVE_BIT > 0,
+            overflow: psw & PSW_OVERFLOW_BIT > 0,
+            brk: psw & PSW_BREAK_BIT > 0,
+            decimal: psw & PSW_DECIMAL_BIT > 0,
+            interrupt: psw & PSW_INTERRUPT_BIT > 0,
+            zero: psw & PSW_ZERO_BIT > 0,
+            carry: psw & PSW_CARRY_BIT > 0,
+        }
+    }
+}
+
+impl From<Flags> for u8 {
+    fn from(flags: Flags) -> u8 {
+        let bit = |set: bool, mask: u8| if set { mask } else { 0 };
+        PSW_ONE_BIT
+            | bit(flags.negative, PSW_NEGATIVE_BIT)
+            | bit(flags.overflow, PSW_OVERFLOW_BIT)
+            | bit(flags.brk, PSW_BREAK_BIT)
+            | bit(flags.decimal, PSW_DECIMAL_BIT)
+            | bit(flags.interrupt, PSW_INTERRUPT_BIT)
+            | bit(flags.zero, PSW_ZERO_BIT)
+            | bit(flags.carry, PSW_CARRY_BIT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Flags, PushSource};
+
+    #[test]
+    fn test_from_u8() {
+        let f = Flags::from(0b1100_0011);
+        assert!(f.negative);
+        assert!(f.overflow);
+        assert!(!f.brk);
+        assert!(!f.decimal);
+        assert!(!f.interrupt);
+        assert!(f.zero);
+        assert!(f.carry);
+    }
+
+    #[test]
+    fn test_into_u8() {
+        let f = Flags {
+            interrupt: true,
+            carry: true,
+            ..Flags::default()
+        };
+        assert_eq!(u8::from(f), 0b0010_0101);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for psw in 0..=255u8 {
+            // bit 5 is always set
+            assert_eq!(u8::from(Flags::from(psw)), psw | 0b0010_0000);
+        }
+    }
+
+    #[test]
+    fn test_pushed_by_instruction() {
+        let f = Flags {
+            carry: true,
+            ..Flags::default()
+        };
+        assert_eq!(f.to_pushed(PushSource::Instruction), 0b0011_0001);
+    }
+
+    #[test]
+    fn test_pushed_by_interrupt() {
+        let f = Flags {
+            brk: true,
+            negative: true,
+            ..Flags::default()
+        };
+        assert_eq!(f.to_pushed(PushSource::Interrupt), 0b1010_0000);
+    }
+
+    #[test]
+    fn test_restore_pulled_ignores_b() {
+        let f = Flags::default();
+        let restored = f.restore_pulled(0xFF);
+        assert!(!restored.brk);
+        assert_eq!(u8::from(restored), 0b1110_1111);
+
+        let f = Flags {
+            brk: true,
+            ..Flags::default()
+        };
+        let restored = f.restore_pulled(0x00);
+        assert!(restored.brk);
+        assert_eq!(u8::from(restored), 0b0011_0000);
+    }
+}
@@ -1,4 +1,12 @@
 mod alu;
+pub mod config;
+pub(crate) mod decoder;
+pub mod determinism;
+pub mod error;
 pub mod execution;
-mod operand_decoder;
+pub mod flags;
+pub mod operand_decoder;
+pub mod rng;
+pub mod stack_check;
 pub mod state;
+pub mod uninit_check;
@@ -1,29 +1,23 @@
 use super::state::State;
 use crate::instruction::operand::Operand;
 
-/// Load 16-bit integer from zero-page
-fn load_le16_zp(state: &State, addr: u8) -> u16 {
-    dbg!(addr);
-    dbg!(addr.wrapping_add(1));
-    let lsb = state.ram_get(addr as u16) as u16;
-    let msb = state.ram_get(addr.wrapping_add(1) as u16) as u16;
-    (msb << 8) | lsb
-}
-
-fn load_le16(state: &State, addr: u16) -> u16 {
-    dbg!(addr);
-    dbg!(addr.wrapping_add(1));
-    let lsb = state.ram_get(addr) as u16;
-    let msb = state.ram_get(addr.wrapping_add(1)) as u16;
-    (msb << 8) | lsb
+/// Read a pointer from zero-page as the CPU does
+/// The high byte address wraps within zero-page, i.e. reading 0xFF takes msb from 0x00
+fn read_u16_zp(state: &State, addr: u8) -> u16 {
+    state.cpu_read_u16(addr as u16, addr.wrapping_add(1) as u16)
 }
 
 /// For a given operand @op, return an address in memory where the value can be found
 /// Example:
 /// ```
-/// let op = Operand::Absolute(0xFFFF);
+/// use nesem::instruction::operand::Operand;
+/// use nesem::interp::operand_decoder::get_pointer;
+/// use nesem::interp::state::State;
+///
+/// let op = Operand::Absolute(0x07FF);
 /// let state = State::new_undefined();
 /// let addr = get_pointer(&op, &state);
+/// assert_eq!(addr, Some(0x07FF));
 /// let value = addr.map(|a| state.ram_get(a));
 /// ```
 pub fn get_pointer(op: &Operand, state: &State) -> Option<u16> {
@@ -37,11 +31,15 @@ pub fn get_pointer(op: &Operand, state: &State) -> Option<u16> {
         Absolute(offset) => Some(*offset),
         AbsoluteX(offset) => Some(offset.wrapping_add(state.x as u16)),
         AbsoluteY(offset) => Some(offset.wrapping_add(state.y as u16)),
-        Indirect(offset) => Some(load_le16(&state, *offset)),
-        IndexedIndirect(table_addr) => Some(load_le16_zp(&state, table_addr.wrapping_add(state.x))),
+        // 6502 bug: JMP ($xxFF) takes the high byte from $xx00, not from the next page
+        Indirect(offset) => {
+            let hi = (*offset & 0xFF00) | (*offset as u8).wrapping_add(1) as u16;
+            Some(state.cpu_read_u16(*offset, hi))
+        }
+        IndexedIndirect(table_addr) => Some(read_u16_zp(state, table_addr.wrapping_add(state.x))),
         IndirectIndexed(table_addr_addr) => {
-            let table_addr = load_le16(&state, *table_addr_addr as u16);
-            Some(table_addr + state.y as u16)
+            let table_addr = read_u16_zp(state, *table_addr_addr);
+            Some(table_addr.wrapping_add(state.y as u16))
         }
     }
 }
@@ -49,12 +47,15 @@ pub fn get_pointer(op: &Operand, state: &State) -> Option<u16> {
 /// For a given operand @op, return its value
 /// Example:
 /// ```
-/// let op = Operand::Absolute(0xFFFE);
-/// let state = State::new_undefined();
-/// state.ram_set(0xFFFE, 0xBA);
-/// state.ram_set(0xFFFF, 0xBA);
-/// let value = get_value(op, state);
-/// assert_eq!(value, 0xBABA);
+/// use nesem::instruction::operand::Operand;
+/// use nesem::interp::operand_decoder::get_value;
+/// use nesem::interp::state::State;
+///
+/// let op = Operand::Absolute(0x07FE);
+/// let mut state = State::new_undefined();
+/// state.load_program(&[0xBA, 0xBA], 0x07FE);
+/// let value = get_value(&op, &state);
+/// assert_eq!(value, Some(0xBABA));
 /// ```
 pub fn get_value(op: &Operand, state: &State) -> Option<u16> {
     use crate::instruction::operand::Operand::*;
@@ -62,7 +63,7 @@ pub fn get_value(op: &Operand, state: &State) -> Option<u16> {
         Implicit => None,
         Accumulator => Some(state.accumulator.into()),
         Immediate(x) => Some(*x as u16),
-        ptr => get_pointer(ptr, state).map(|p| load_le16(state, p)),
+        ptr => get_pointer(ptr, state).map(|p| state.cpu_read_u16(p, p.wrapping_add(1))),
     }
 }
 
@@ -72,7 +73,7 @@ pub fn get_u8(op: &Operand, state: &State) -> Option<u8> {
         Implicit => None,
         Accumulator => Some(state.accumulator.into()),
         Immediate(x) => Some(*x),
-        ptr => get_pointer(ptr, state).map(|p| state.ram_get(p)),
+        ptr => get_pointer(ptr, state).map(|p| state.cpu_read(p)),
     }
 }
 
@@ -83,7 +84,7 @@ pub fn set_u8(op: &Operand, val: u8, state: &mut State) -> Result<(), ()> {
 
     match ptr {
         Some(p) => {
-            state.ram_set(p, val);
+            state.cpu_write(p, val);
             Ok(())
         }
         None => match op {
@@ -223,4 +224,24 @@ mod tests {
         state.ram_set(0x00, 0xBA);
         assert_eq!(get_pointer(&op, &state), Some(0xBAFC));
     }
+
+    #[test]
+    fn indirect_indexed_wrap() {
+        let op = Operand::IndirectIndexed(0xFF);
+        let mut state = State::new_undefined();
+        state.y = 0x04;
+        state.ram_set(0xFF, 0xFC);
+        state.ram_set(0x00, 0x01);
+        assert_eq!(get_pointer(&op, &state), Some(0x0200));
+    }
+
+    #[test]
+    fn indirect_page_wrap() {
+        let op = Operand::Indirect(0x02FF);
+        let mut state = State::new_undefined();
+        state.ram_set(0x02FF, 0x34);
+        state.ram_set(0x0200, 0x12);
+        state.ram_set(0x0300, 0x56);
+        assert_eq!(get_pointer(&op, &state), Some(0x1234));
+    }
 }
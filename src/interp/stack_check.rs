@@ -0,0 +1,269 @@
+/// Suspicious use of the stack found while the stack check is enabled
+/// `pc` is the address of the instruction, as in `UninitRead`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum StackAnomaly {
+    /// push with SP = 0x00 wrapped SP around to 0xFF
+    Overflow { pc: u16 },
+    /// pop with SP = 0xFF wrapped SP around to 0x00
+    Underflow { pc: u16 },
+    /// RTS popped @addr, but the matching JSR pushed @expected
+    /// (None if no JSR pushed the popped address at all)
+    UnmatchedReturn {
+        pc: u16,
+        addr: u16,
+        expected: Option<u16>,
+    },
+}
+
+/// Shadow stack of return addresses pushed by JSR, and anomalies found so far
+#[derive(Clone, Debug, Default)]
+pub struct StackCheck {
+    /// SP before the push and the pushed return address
+    shadow: Vec<(u8, u16)>,
+    /// PC of the instruction being executed
+    pc: u16,
+    anomalies: Vec<StackAnomaly>,
+}
+
+impl StackCheck {
+    pub fn new() -> StackCheck {
+        StackCheck::default()
+    }
+
+    /// Called when the instruction at @pc starts executing
+    pub fn set_pc(&mut self, pc: u16) {
+        self.pc = pc;
+    }
+
+    /// Called before a byte is pushed with stack pointer @sp
+    pub fn on_push(&mut self, sp: u8) {
+        if sp == 0x00 {
+            self.anomalies.push(StackAnomaly::Overflow { pc: self.pc });
+        }
+    }
+
+    /// Called before a byte is popped with stack pointer @sp
+    pub fn on_pop(&mut self, sp: u8) {
+        if sp == 0xFF {
+            self.anomalies.push(StackAnomaly::Underflow { pc: self.pc });
+        }
+    }
+
+    /// Called when JSR pushes @addr with stack pointer @sp
+    pub fn on_call(&mut self, sp: u8, addr: u16) {
+        self.shadow.push((sp, addr));
+    }
+
+    /// Called when RTS pops @addr with stack pointer @sp after the pop
+    pub fn on_return(&mut self, sp: u8, addr: u16) {
+        // frames below SP were discarded without RTS (e.g. by TXS or PLA)
+        while let Some(&(frame_sp, _)) = self.shadow.last() {
+            if frame_sp >= sp {
+                break;
+            }
+            self.shadow.pop();
+        }
+
+        let expected = match self.shadow.last() {
+            Some(&(frame_sp, frame_addr)) if frame_sp == sp => {
+                self.shadow.pop();
+                Some(frame_addr)
+            }
+            _ => None,
+        };
+
+        if expected != Some(addr) {
+            self.anomalies.push(StackAnomaly::UnmatchedReturn {
+                pc: self.pc,
+                addr,
+                expected,
+            });
+        }
+    }
+
+    /// Forget all pending JSR frames, anomalies found so far are kept
+    pub fn clear_frames(&mut self) {
+        self.shadow.clear();
+    }
+
+    /// Return anomalies found so far
+    pub fn anomalies(&self) -> &[StackAnomaly] {
+        &self.anomalies
+    }
+
+    /// Return and forget anomalies found so far
+    pub fn take_anomalies(&mut self) -> Vec<StackAnomaly> {
+        std::mem::take(&mut self.anomalies)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StackAnomaly;
+    use crate::asm::assembler::assemble;
+    use crate::interp::config::Config;
+    use crate::interp::state::{PowerOnRamPattern, State};
+
+    fn run(source: &str) -> Vec<StackAnomaly> {
+        let mut st = State::new(&Config {
+            stack_check: true,
+            ..Config::default()
+        });
+        st.sp = 0xFF;
+        st.load_program(&assemble(source).unwrap(), 0x0600);
+        st.run_until_brk(1000).unwrap();
+        st.stack_check_mut().unwrap().take_anomalies()
+    }
+
+    #[test]
+    fn nested_calls() {
+        let anomalies = run("
+            .org $0600
+                    jsr outer
+                    brk
+            outer:  jsr inner
+                    rts
+            inner:  rts
+            ");
+        assert_eq!(anomalies, vec![]);
+    }
+
+    #[test]
+    fn rts_trick() {
+        // push $0706 high byte first and "return" to $0706 + 1
+        let anomalies = run("
+            .org $0600
+                    lda #$07
+                    pha
+                    lda #$06
+                    pha
+                    rts
+                    .byte $02
+            .org $0707
+                    brk
+            ");
+        assert_eq!(
+            anomalies,
+            vec![StackAnomaly::UnmatchedReturn {
+                pc: 0x0606,
+                addr: 0x0706,
+                expected: None
+            }]
+        );
+    }
+
+    #[test]
+    fn smashed_return_address() {
+        let anomalies = run("
+            .org $0600
+                    jsr f
+                    brk
+            f:      pla
+                    pla
+                    lda #$07
+                    pha
+                    lda #$02
+                    pha
+                    rts
+            .org $0703
+                    brk
+            ");
+        assert!(matches!(
+            anomalies[..],
+            [StackAnomaly::UnmatchedReturn {
+                pc: 0x060C,
+                addr: 0x0702,
+                expected: Some(0x0602),
+            }]
+        ));
+    }
+
+    #[test]
+    fn wrap() {
+        let anomalies = run("
+            .org $0600
+                    ldx #$00
+                    txs
+                    pha
+                    pla
+                    brk
+            ");
+        assert!(matches!(
+            anomalies[..],
+            [
+                StackAnomaly::Overflow { .. },
+                StackAnomaly::Underflow { .. }
+            ]
+        ));
+    }
+
+    /// Leave one JSR frame on the shadow stack, then reset with @reset and run the RTS trick
+    fn trick_after_reset(reset: fn(&mut State)) -> Vec<StackAnomaly> {
+        let mut st = State::new(&Config {
+            stack_check: true,
+            ..Config::default()
+        });
+        st.sp = 0xFF;
+        st.load_program(&assemble(".org $0604\njsr $0700").unwrap(), 0x0604);
+        st.load_program(&[0x00], 0x0700);
+        st.run_until_brk(10).unwrap();
+        st.stack_check_mut().unwrap().take_anomalies();
+
+        reset(&mut st);
+        // the trick pushes exactly the address the abandoned JSR pushed
+        let trick = assemble(
+            "
+            .org $0600
+                    lda #$06
+                    pha
+                    lda #$06
+                    pha
+                    rts
+            ",
+        )
+        .unwrap();
+        st.load_program(&trick, 0x0600);
+        st.sp = 0xFF;
+        st.run_until_brk(10).unwrap();
+        st.stack_check_mut().unwrap().take_anomalies()
+    }
+
+    #[test]
+    fn power_cycle_resets_shadow_stack() {
+        let anomalies = trick_after_reset(|st| st.power_cycle(PowerOnRamPattern::Zeros, 0x0600));
+        assert!(matches!(
+            anomalies[..],
+            [StackAnomaly::UnmatchedReturn { expected: None, .. }]
+        ));
+    }
+
+    #[test]
+    fn soft_reset_drops_frames() {
+        let anomalies = trick_after_reset(|st| st.soft_reset(0x0600));
+        assert!(matches!(
+            anomalies[..],
+            [StackAnomaly::UnmatchedReturn { expected: None, .. }]
+        ));
+    }
+
+    #[test]
+    fn soft_reset_keeps_anomalies() {
+        let mut st = State::new_undefined();
+        st.set_stack_check(true);
+        st.sp = 0xFF;
+        st.stack_pop();
+        st.soft_reset(0x0600);
+        assert_eq!(
+            st.stack_check().unwrap().anomalies(),
+            &[StackAnomaly::Underflow { pc: 0 }]
+        );
+        st.power_cycle(PowerOnRamPattern::Zeros, 0x0600);
+        assert_eq!(st.stack_check().unwrap().anomalies(), &[]);
+    }
+
+    #[test]
+    fn disabled_by_default() {
+        let st = State::new(&Config::default());
+        assert!(st.stack_check().is_none());
+    }
+}
@@ -1,4 +1,33 @@
+use super::config::{Config, UnstableOpcodes};
+use super::error::Error;
+use super::execution::{is_idle_loop, step};
+use super::flags::*;
+use super::rng::Rng;
+use super::stack_check::StackCheck;
+use super::uninit_check::UninitCheck;
+use crate::trace::logger::{Level, Target};
+#[cfg(feature = "trace")]
+use crate::trace::logger::{Logger, Record};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Content of RAM right after the console is powered on
+/// Real hardware leaves RAM in an unspecified state; some games depend on it
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PowerOnRamPattern {
+    /// all bytes are 0x00
+    Zeros,
+    /// all bytes are 0xFF
+    Ones,
+    /// 4 bytes of 0x00 followed by 4 bytes of 0xFF, repeated
+    Alternating,
+    /// pseudo-random bytes drawn from the seeded rng of the state
+    Random,
+}
+
 /// Holds state of a 6502 interpreter
+/// Everything an interpreter needs lives here, there is no global state.
+/// State is `Send`, so independent interpreters can run on separate threads.
 pub struct State {
     /// Program counter
     pub pc: u16,
@@ -18,24 +47,42 @@ pub struct State {
     pub y: u8,
 
     /// Content of ram
-    ram: [u8; 0x800],
+    ram: [u8; RAM_SIZE],
     /// Content of ppu registers
     ppu_registers: [u8; 0x8],
     /// Content of apu input
     apu_input: [u8; 0x18],
+
+    /// Ram addresses which keep their value regardless of writes
+    frozen: BTreeMap<u16, u8>,
+    /// Source of anything the hardware leaves unspecified
+    rng: Rng,
+    /// Shadow stack used to find stack anomalies, None when disabled
+    stack_check: Option<StackCheck>,
+    /// Ram bytes written since power-on, None when disabled
+    uninit_check: Option<UninitCheck>,
+    /// Keep running through illegal opcodes and accesses outside ram, see `Config::tolerant`
+    tolerant: bool,
+    /// Errors `step` ran through in tolerant mode, oldest first
+    tolerated_errors: Vec<Error>,
+    /// Behavior of unstable unofficial opcodes, None when they are illegal
+    unstable_opcodes: Option<UnstableOpcodes>,
+    /// Receiver of diagnostics, None when nobody listens
+    #[cfg(feature = "trace")]
+    logger: Option<Box<dyn Logger>>,
 }
 
-const PSW_CARRY_BIT: u8 = 1 << 0;
-const PSW_ZERO_BIT: u8 = 1 << 1;
-const PSW_INTERRUPT_BIT: u8 = 1 << 2;
-const PSW_DECIMAL_BIT: u8 = 1 << 3;
-const PSW_BREAK_BIT: u8 = 1 << 4;
-const PSW_ONE_BIT: u8 = 1 << 5;
-const PSW_OVERFLOW_BIT: u8 = 1 << 6;
-const PSW_NEGATIVE_BIT: u8 = 1 << 7;
+/// Size of the internal ram
+pub const RAM_SIZE: usize = 0x800;
 
 const STACK_OFFSET: u16 = 0x100;
 
+/// Opcode of BRK, which ends programs run by `run_until_brk`
+pub const BRK_OPCODE: u8 = 0x00;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
 /// generate getter and setter for a given psw bit in state
 macro_rules! psw_getset {
     ($getter:ident, $setter:ident, $mask:expr) => {
@@ -64,60 +111,491 @@ impl State {
             accumulator: 0,
             x: 0,
             y: 0,
-            ram: [0; 0x800],
+            ram: [0; RAM_SIZE],
             ppu_registers: [0; 0x8],
             apu_input: [0; 0x18],
+            frozen: BTreeMap::new(),
+            rng: Rng::new(0),
+            stack_check: None,
+            uninit_check: None,
+            tolerant: false,
+            tolerated_errors: Vec::new(),
+            unstable_opcodes: None,
+            #[cfg(feature = "trace")]
+            logger: None,
+        }
+    }
+
+    /// create a new state with rng seeded and RAM filled according to @config
+    pub fn new(config: &Config) -> State {
+        let mut state = State::new_undefined();
+        state.rng = Rng::new(config.seed);
+        state.fill_ram(config.ram_pattern);
+        state.set_stack_check(config.stack_check);
+        state.set_uninit_check(config.uninit_check);
+        state.tolerant = config.tolerant;
+        state.unstable_opcodes = config.unstable_opcodes;
+        state
+    }
+
+    pub fn rng(&self) -> &Rng {
+        &self.rng
+    }
+
+    pub fn set_rng(&mut self, rng: Rng) {
+        self.rng = rng;
+    }
+
+    /// Enable or disable recording of stack anomalies
+    /// Enabling starts with an empty shadow stack
+    pub fn set_stack_check(&mut self, enabled: bool) {
+        self.stack_check = if enabled {
+            Some(StackCheck::new())
+        } else {
+            None
+        };
+    }
+
+    pub fn stack_check(&self) -> Option<&StackCheck> {
+        self.stack_check.as_ref()
+    }
+
+    pub fn stack_check_mut(&mut self) -> Option<&mut StackCheck> {
+        self.stack_check.as_mut()
+    }
+
+    /// Enable or disable reporting of reads of ram never written
+    /// Enabling treats the whole ram as not written yet
+    pub fn set_uninit_check(&mut self, enabled: bool) {
+        self.uninit_check = if enabled {
+            Some(UninitCheck::new(self.ram.len()))
+        } else {
+            None
+        };
+    }
+
+    pub fn uninit_check(&self) -> Option<&UninitCheck> {
+        self.uninit_check.as_ref()
+    }
+
+    pub fn uninit_check_mut(&mut self) -> Option<&mut UninitCheck> {
+        self.uninit_check.as_mut()
+    }
+
+    pub fn tolerant(&self) -> bool {
+        self.tolerant
+    }
+
+    pub fn set_tolerant(&mut self, tolerant: bool) {
+        self.tolerant = tolerant;
+    }
+
+    pub fn unstable_opcodes(&self) -> Option<UnstableOpcodes> {
+        self.unstable_opcodes
+    }
+
+    pub fn set_unstable_opcodes(&mut self, unstable_opcodes: Option<UnstableOpcodes>) {
+        self.unstable_opcodes = unstable_opcodes;
+    }
+
+    /// Remember @error which `step` ran through in tolerant mode
+    pub(crate) fn tolerate(&mut self, error: Error) {
+        self.log(
+            Target::Interp,
+            Level::Warn,
+            format_args!("tolerated {:?}", error),
+        );
+        self.tolerated_errors.push(error);
+    }
+
+    /// Errors `step` ran through in tolerant mode, oldest first
+    pub fn tolerated_errors(&self) -> &[Error] {
+        &self.tolerated_errors
+    }
+
+    /// Return the tolerated errors and forget them
+    /// A game stuck in a bad state can produce one every frame, so take them regularly
+    pub fn take_tolerated_errors(&mut self) -> Vec<Error> {
+        std::mem::take(&mut self.tolerated_errors)
+    }
+
+    /// Pass diagnostics of this interpreter to @logger, None stops logging
+    #[cfg(feature = "trace")]
+    pub fn set_logger(&mut self, logger: Option<Box<dyn Logger>>) {
+        self.logger = logger;
+    }
+
+    /// Return true if a record of @level from @target would be logged
+    /// Use it to skip preparing expensive records, always false without the `trace` feature
+    pub(crate) fn log_enabled(&self, target: Target, level: Level) -> bool {
+        #[cfg(feature = "trace")]
+        if let Some(logger) = &self.logger {
+            return logger.enabled(target, level);
+        }
+        let _ = (target, level);
+        false
+    }
+
+    /// Pass a record to the logger, does nothing without the `trace` feature
+    pub(crate) fn log(&mut self, target: Target, level: Level, args: fmt::Arguments) {
+        #[cfg(feature = "trace")]
+        if let Some(logger) = &mut self.logger {
+            if logger.enabled(target, level) {
+                logger.log(&Record {
+                    target,
+                    level,
+                    args,
+                });
+            }
+        }
+        let _ = (target, level, args);
+    }
+
+    /// Perform the sequence triggered by the reset button
+    /// RAM and registers other than SP, PC and I are preserved
+    /// Pending JSR frames of the stack check are dropped, its anomalies are kept
+    /// @reset_vector is the content of 0xFFFC, which lives in cartridge space outside of ram
+    pub fn soft_reset(&mut self, reset_vector: u16) {
+        // reset performs 3 stack reads without writing anything
+        self.sp = self.sp.wrapping_sub(3);
+        self.set_interrupt(true);
+        self.pc = reset_vector;
+        self.log(
+            Target::Interp,
+            Level::Info,
+            format_args!("soft reset to {:04X}", reset_vector),
+        );
+        // execution restarts from scratch, no pending JSR can be returned to
+        if let Some(check) = &mut self.stack_check {
+            check.clear_frames();
+        }
+    }
+
+    /// Turn the console off and on again
+    /// RAM gets filled with @pattern, registers are set to their power-on values,
+    /// enabled debug checks start over and execution starts at @reset_vector
+    pub fn power_cycle(&mut self, pattern: PowerOnRamPattern, reset_vector: u16) {
+        self.log(
+            Target::Interp,
+            Level::Info,
+            format_args!("power cycle with {:?} ram", pattern),
+        );
+        self.accumulator = 0;
+        self.x = 0;
+        self.y = 0;
+        // reset sequence brings this to 0xFD
+        self.sp = 0;
+        // B is not a real bit of P, it only appears in pushed copies
+        self.psw = PSW_ONE_BIT | PSW_INTERRUPT_BIT;
+        self.fill_ram(pattern);
+        if let Some(check) = &mut self.uninit_check {
+            check.reset();
+        }
+        if self.stack_check.is_some() {
+            self.stack_check = Some(StackCheck::new());
+        }
+        self.soft_reset(reset_vector);
+    }
+
+    fn fill_ram(&mut self, pattern: PowerOnRamPattern) {
+        match pattern {
+            PowerOnRamPattern::Zeros => self.ram.iter_mut().for_each(|b| *b = 0x00),
+            PowerOnRamPattern::Ones => self.ram.iter_mut().for_each(|b| *b = 0xFF),
+            PowerOnRamPattern::Alternating => {
+                for (i, b) in self.ram.iter_mut().enumerate() {
+                    *b = if i & 0x4 == 0 { 0x00 } else { 0xFF };
+                }
+            }
+            PowerOnRamPattern::Random => self.rng.fill(&mut self.ram),
         }
     }
 
+    /// Return ram byte at @addr
+    /// There is no MMU yet, @addr must be below `RAM_SIZE`, otherwise this panics
     pub fn ram_get(&self, addr: u16) -> u8 {
         self.ram[addr as usize]
     }
 
+    /// Read @addr the way the CPU does, unlike `ram_get` this is seen by the uninit check
+    /// Addresses outside ram read as open bus, approximated by the high byte of @addr,
+    /// which is usually the last byte the CPU put on the bus
+    pub fn cpu_read(&self, addr: u16) -> u8 {
+        if let Some(check) = &self.uninit_check {
+            if (addr as usize) < RAM_SIZE {
+                check.on_read(addr);
+            }
+        }
+        self.cpu_peek(addr)
+    }
+
+    /// Return what `cpu_read` would, without being seen by the uninit check
+    pub fn cpu_peek(&self, addr: u16) -> u8 {
+        if addr as usize >= RAM_SIZE {
+            return (addr >> 8) as u8;
+        }
+        self.ram[addr as usize]
+    }
+
+    /// Read a little-endian 16-bit integer through `cpu_read`
+    /// The low byte comes from @lo_addr and the high byte from @hi_addr
+    pub fn cpu_read_u16(&self, lo_addr: u16, hi_addr: u16) -> u16 {
+        let lsb = self.cpu_read(lo_addr) as u16;
+        let msb = self.cpu_read(hi_addr) as u16;
+        (msb << 8) | lsb
+    }
+
+    /// Store @value to ram at @addr, unless the address is frozen
+    /// @addr must be below `RAM_SIZE`, otherwise this panics
     pub fn ram_set(&mut self, addr: u16, value: u8) {
-        self.ram[addr as usize] = value;
+        if let Some(check) = &mut self.uninit_check {
+            check.on_write(addr);
+        }
+        self.ram[addr as usize] = *self.frozen.get(&addr).unwrap_or(&value);
+    }
+
+    /// Write @value to @addr the way the CPU does
+    /// Writes outside ram go nowhere, there is no MMU yet
+    pub fn cpu_write(&mut self, addr: u16, value: u8) {
+        if (addr as usize) < RAM_SIZE {
+            self.ram_set(addr, value);
+        }
+    }
+
+    /// Set @addr to @value and ignore any further writes to it until `unfreeze`
+    pub fn freeze(&mut self, addr: u16, value: u8) {
+        self.frozen.insert(addr, value);
+        self.ram_set(addr, value);
+    }
+
+    pub fn unfreeze(&mut self, addr: u16) {
+        self.frozen.remove(&addr);
+    }
+
+    pub fn is_frozen(&self, addr: u16) -> bool {
+        self.frozen.contains_key(&addr)
+    }
+
+    /// Copy @program to ram starting at @at and point PC to it
+    pub fn load_program(&mut self, program: &[u8], at: u16) {
+        for (i, byte) in program.iter().enumerate() {
+            self.ram_set(at.wrapping_add(i as u16), *byte);
+        }
+        self.pc = at;
+    }
+
+    /// Execute instructions until PC points to BRK
+    /// BRK itself is not executed. Return number of executed instructions, or an error
+    /// if an instruction can't be executed or BRK is not reached within @max_steps.
+    pub fn run_until_brk(&mut self, max_steps: usize) -> Result<usize, Error> {
+        let mut count = 0;
+        loop {
+            if (self.pc as usize) < RAM_SIZE && self.ram_get(self.pc) == BRK_OPCODE {
+                return Ok(count);
+            }
+            if count == max_steps {
+                return Err(Error::StepLimit { steps: max_steps });
+            }
+            step(self)?;
+            count += 1;
+        }
+    }
+
+    /// Return true if PC is in a loop which only waits for an interrupt
+    /// Frontends may skip ahead to the next event instead of emulating it.
+    pub fn is_idle_loop(&self) -> bool {
+        is_idle_loop(self)
+    }
+
+    /// Return content of the whole ram
+    pub fn ram(&self) -> &[u8] {
+        &self.ram
+    }
+
+    pub fn ram_mut(&mut self) -> &mut [u8] {
+        &mut self.ram
+    }
+
+    /// Read a little-endian 16-bit integer from @addr and @addr + 1
+    /// Both addresses must be in ram (@addr below `RAM_SIZE` - 1), otherwise this panics
+    pub fn read_u16_le(&self, addr: u16) -> u16 {
+        let lsb = self.ram_get(addr) as u16;
+        let msb = self.ram_get(addr.wrapping_add(1)) as u16;
+        (msb << 8) | lsb
+    }
+
+    /// Read a little-endian 16-bit integer from zero-page
+    /// The high byte address wraps within zero-page, i.e. reading 0xFF takes msb from 0x00
+    pub fn read_u16_zp_wrapped(&self, addr: u8) -> u16 {
+        let lsb = self.ram_get(addr as u16) as u16;
+        let msb = self.ram_get(addr.wrapping_add(1) as u16) as u16;
+        (msb << 8) | lsb
+    }
+
+    /// Read a little-endian 16-bit integer the way JMP (indirect) does
+    /// The high byte address wraps within the page of @addr, i.e. reading 0x02FF takes msb from 0x0200
+    /// @addr must be below `RAM_SIZE`, otherwise this panics
+    pub fn read_u16_page_wrapped(&self, addr: u16) -> u16 {
+        let lsb = self.ram_get(addr) as u16;
+        let msb_addr = (addr & 0xFF00) | (addr as u8).wrapping_add(1) as u16;
+        let msb = self.ram_get(msb_addr) as u16;
+        (msb << 8) | lsb
+    }
+
+    /// Write @value as a little-endian 16-bit integer to @addr and @addr + 1
+    /// Both addresses must be in ram (@addr below `RAM_SIZE` - 1), otherwise this panics
+    pub fn write_u16_le(&mut self, addr: u16, value: u16) {
+        self.ram_set(addr, value as u8);
+        self.ram_set(addr.wrapping_add(1), (value >> 8) as u8);
     }
 
     /// return stack pointer
     /// the address where to store newly-pushed element of stack
     fn get_sp(&self) -> u16 {
-        STACK_OFFSET + self.pc
+        STACK_OFFSET + self.sp as u16
     }
 
     pub fn stack_push(&mut self, val: u8) {
+        if let Some(check) = &mut self.stack_check {
+            check.on_push(self.sp);
+        }
         self.ram_set(self.get_sp(), val);
         self.sp = self.sp.wrapping_sub(1);
     }
 
     pub fn stack_pop(&mut self) -> u8 {
+        if let Some(check) = &mut self.stack_check {
+            check.on_pop(self.sp);
+        }
         self.sp = self.sp.wrapping_add(1);
-        self.ram_get(self.get_sp())
+        self.cpu_read(self.get_sp())
+    }
+
+    pub fn flags(&self) -> Flags {
+        Flags::from(self.psw)
+    }
+
+    pub fn set_flags(&mut self, flags: Flags) {
+        self.psw = flags.into();
+    }
+
+    /// push the status word, used by PHP, BRK and interrupts
+    /// B is set in the pushed copy depending on @source
+    pub fn push_flags(&mut self, source: PushSource) {
+        self.stack_push(self.flags().to_pushed(source));
+    }
+
+    /// pull the status word, used by PLP and RTI
+    /// B and bit 5 of the pulled byte are ignored
+    pub fn pull_flags(&mut self) {
+        let pulled = self.stack_pop();
+        self.set_flags(self.flags().restore_pulled(pulled));
+    }
+
+    /// Push @value high byte first, as JSR and interrupts do
+    pub fn push_u16(&mut self, value: u16) {
+        self.stack_push((value >> 8) as u8);
+        self.stack_push(value as u8);
+    }
+
+    /// Pop a value pushed by `push_u16`
+    pub fn pop_u16(&mut self) -> u16 {
+        let lsb = self.stack_pop() as u16;
+        let msb = self.stack_pop() as u16;
+        (msb << 8) | lsb
     }
 
     pub fn push_pc(&mut self) {
-        self.stack_push(self.pc as u8);
-        self.stack_push((self.pc >> 8) as u8);
+        self.push_u16(self.pc);
     }
 
     pub fn pop_pc(&mut self) {
-        self.pc = 0;
-        self.pc = ((self.stack_pop() as u16) << 8);
-        self.pc |= self.stack_pop() as u16;
+        self.pc = self.pop_u16();
+    }
+
+    /// Return a 64-bit FNV-1a hash of registers, all memory and the rng state
+    /// Two states with the same fingerprint are (with high probability) identical,
+    /// which is used to catch nondeterminism when replaying recorded inputs
+    pub fn fingerprint(&self) -> u64 {
+        let registers = [
+            self.pc as u8,
+            (self.pc >> 8) as u8,
+            self.sp,
+            self.psw,
+            self.accumulator,
+            self.x,
+            self.y,
+        ];
+        let rng = self.rng.state().to_le_bytes();
+
+        registers
+            .iter()
+            .chain(self.ram.iter())
+            .chain(self.ppu_registers.iter())
+            .chain(self.apu_input.iter())
+            .chain(rng.iter())
+            .fold(FNV_OFFSET_BASIS, |hash, byte| {
+                (hash ^ *byte as u64).wrapping_mul(FNV_PRIME)
+            })
+    }
+
+    /// Return the status word as `NV-BDIZC`
+    /// set flags are uppercase, cleared flags are lowercase, the unused bit is always `-`
+    pub fn flags_string(&self) -> String {
+        "NV-BDIZC"
+            .chars()
+            .enumerate()
+            .map(|(i, c)| {
+                let set = self.psw & (0x80 >> i) > 0;
+                match c {
+                    '-' => '-',
+                    c if set => c,
+                    c => c.to_ascii_lowercase(),
+                }
+            })
+            .collect()
     }
 
     psw_getset!(get_carry, set_carry, PSW_CARRY_BIT);
     psw_getset!(get_zero, set_zero, PSW_ZERO_BIT);
     psw_getset!(get_interrupt, set_interrupt, PSW_INTERRUPT_BIT);
-    // we don't support PSW_DECIMAL_BIT
+    // the flag can be set, but decimal mode arithmetic is not supported
+    psw_getset!(get_decimal, set_decimal, PSW_DECIMAL_BIT);
     psw_getset!(get_break, set_break, PSW_BREAK_BIT);
     // get/set for PSW_ONE_BIT is useless
     psw_getset!(get_overflow, set_overflow, PSW_OVERFLOW_BIT);
     psw_getset!(get_negative, set_negative, PSW_NEGATIVE_BIT);
 }
 
+/// Compact status line, e.g. `A:00 X:00 Y:00 P:nv-bdizc SP:FD PC:C000`
+impl fmt::Display for State {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "A:{:02X} X:{:02X} Y:{:02X} P:{} SP:{:02X} PC:{:04X}",
+            self.accumulator,
+            self.x,
+            self.y,
+            self.flags_string(),
+            self.sp,
+            self.pc
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::State;
+    use super::{PowerOnRamPattern, State};
+    use crate::interp::config::Config;
+    use crate::interp::error::Error;
+    use crate::interp::rng::Rng;
+
+    fn with_pattern(seed: u64, ram_pattern: PowerOnRamPattern) -> State {
+        State::new(&Config {
+            seed,
+            ram_pattern,
+            ..Config::default()
+        })
+    }
 
     #[test]
     fn test_psw() {
@@ -157,4 +635,263 @@ mod tests {
         assert_eq!(true, st.get_overflow());
         assert_eq!(false, st.get_negative());
     }
+
+    #[test]
+    fn test_fingerprint() {
+        let mut a = State::new_undefined();
+        let b = State::new_undefined();
+        assert_eq!(a.fingerprint(), b.fingerprint());
+
+        a.ram_set(0x7FF, 1);
+        assert_ne!(a.fingerprint(), b.fingerprint());
+        a.ram_set(0x7FF, 0);
+        assert_eq!(a.fingerprint(), b.fingerprint());
+
+        a.x = 1;
+        assert_ne!(a.fingerprint(), b.fingerprint());
+        a.x = 0;
+
+        a.set_rng(Rng::new(1));
+        assert_ne!(a.fingerprint(), b.fingerprint());
+    }
+
+    #[test]
+    fn test_u16_le() {
+        let mut st = State::new_undefined();
+        st.write_u16_le(0x0120, 0xBAFC);
+        assert_eq!(st.ram_get(0x0120), 0xFC);
+        assert_eq!(st.ram_get(0x0121), 0xBA);
+        assert_eq!(st.read_u16_le(0x0120), 0xBAFC);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_u16_le_past_ram() {
+        let st = State::new_undefined();
+        st.read_u16_le(0x07FF);
+    }
+
+    #[test]
+    fn test_u16_zp_wrapped() {
+        let mut st = State::new_undefined();
+        st.ram_set(0xFF, 0xFC);
+        st.ram_set(0x00, 0xBA);
+        st.ram_set(0x100, 0x12);
+        assert_eq!(st.read_u16_zp_wrapped(0xFF), 0xBAFC);
+        assert_eq!(st.read_u16_le(0xFF), 0x12FC);
+    }
+
+    #[test]
+    fn test_soft_reset() {
+        let mut st = State::new_undefined();
+        st.sp = 0xFD;
+        st.accumulator = 0x12;
+        st.ram_set(0x10, 0x34);
+        st.soft_reset(0xC000);
+        assert_eq!(st.pc, 0xC000);
+        assert_eq!(st.sp, 0xFA);
+        assert!(st.get_interrupt());
+        assert_eq!(st.accumulator, 0x12);
+        assert_eq!(st.ram_get(0x10), 0x34);
+    }
+
+    #[test]
+    fn test_power_cycle() {
+        let mut st = State::new_undefined();
+        st.accumulator = 0x12;
+        st.x = 0x34;
+        st.set_carry(true);
+        st.power_cycle(PowerOnRamPattern::Ones, 0x8000);
+        assert_eq!(st.pc, 0x8000);
+        assert_eq!(st.sp, 0xFD);
+        assert_eq!((st.accumulator, st.x, st.y), (0, 0, 0));
+        assert!(!st.get_carry());
+        assert!(!st.get_break());
+        assert!(st.get_interrupt());
+        assert!((0..0x800).all(|a| st.ram_get(a) == 0xFF));
+    }
+
+    #[test]
+    fn test_u16_page_wrapped() {
+        let mut st = State::new_undefined();
+        st.ram_set(0x02FF, 0xFC);
+        st.ram_set(0x0200, 0xBA);
+        st.ram_set(0x0300, 0x12);
+        assert_eq!(st.read_u16_page_wrapped(0x02FF), 0xBAFC);
+        assert_eq!(st.read_u16_le(0x02FF), 0x12FC);
+        st.ram_set(0x0201, 0x34);
+        assert_eq!(st.read_u16_page_wrapped(0x0200), 0x34BA);
+    }
+
+    #[test]
+    fn test_power_on_zeros_ones() {
+        let st = with_pattern(0, PowerOnRamPattern::Zeros);
+        assert!((0..0x800).all(|a| st.ram_get(a) == 0x00));
+        let st = with_pattern(0, PowerOnRamPattern::Ones);
+        assert!((0..0x800).all(|a| st.ram_get(a) == 0xFF));
+    }
+
+    #[test]
+    fn test_power_on_alternating() {
+        let st = with_pattern(0, PowerOnRamPattern::Alternating);
+        assert_eq!(st.ram_get(0x0), 0x00);
+        assert_eq!(st.ram_get(0x3), 0x00);
+        assert_eq!(st.ram_get(0x4), 0xFF);
+        assert_eq!(st.ram_get(0x7), 0xFF);
+        assert_eq!(st.ram_get(0x8), 0x00);
+        assert_eq!(st.ram_get(0x7FF), 0xFF);
+    }
+
+    #[test]
+    fn test_power_on_random() {
+        let a = with_pattern(42, PowerOnRamPattern::Random);
+        let b = with_pattern(42, PowerOnRamPattern::Random);
+        let c = with_pattern(43, PowerOnRamPattern::Random);
+        assert_eq!(a.fingerprint(), b.fingerprint());
+        assert_ne!(a.fingerprint(), c.fingerprint());
+        assert!((0..0x800).any(|addr| a.ram_get(addr) != 0));
+    }
+
+    #[test]
+    fn test_flags_string() {
+        let mut st = State::new_undefined();
+        assert_eq!(st.flags_string(), "nv-bdizc");
+        st.set_negative(true);
+        st.set_carry(true);
+        st.set_break(true);
+        assert_eq!(st.flags_string(), "Nv-BdizC");
+    }
+
+    #[test]
+    fn test_display() {
+        let mut st = State::new_undefined();
+        st.accumulator = 0x01;
+        st.x = 0xAB;
+        st.y = 0x0F;
+        st.sp = 0xFD;
+        st.pc = 0xC000;
+        st.set_zero(true);
+        assert_eq!(st.to_string(), "A:01 X:AB Y:0F P:nv-bdiZc SP:FD PC:C000");
+    }
+
+    #[test]
+    fn test_freeze() {
+        let mut st = State::new_undefined();
+        st.freeze(0x10, 0x63);
+        assert!(st.is_frozen(0x10));
+        assert_eq!(st.ram_get(0x10), 0x63);
+
+        st.ram_set(0x10, 0x00);
+        assert_eq!(st.ram_get(0x10), 0x63);
+
+        st.unfreeze(0x10);
+        st.ram_set(0x10, 0x00);
+        assert_eq!(st.ram_get(0x10), 0x00);
+    }
+
+    #[test]
+    fn test_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<State>();
+
+        let handles: Vec<_> = (0..4u8)
+            .map(|i| {
+                std::thread::spawn(move || {
+                    let mut st = with_pattern(i as u64, PowerOnRamPattern::Random);
+                    st.ram_set(0, i);
+                    st
+                })
+            })
+            .collect();
+        for (i, h) in handles.into_iter().enumerate() {
+            assert_eq!(h.join().unwrap().ram_get(0), i as u8);
+        }
+    }
+
+    #[test]
+    fn test_stack() {
+        let mut st = State::new_undefined();
+        st.sp = 0xFF;
+        st.stack_push(1);
+        st.pc = 0x1234;
+        st.stack_push(2);
+        assert_eq!(st.sp, 0xFD);
+        assert_eq!(st.ram_get(0x1FF), 1);
+        assert_eq!(st.ram_get(0x1FE), 2);
+        assert_eq!(st.stack_pop(), 2);
+        assert_eq!(st.stack_pop(), 1);
+    }
+
+    #[test]
+    fn test_push_pop_u16() {
+        let mut st = State::new_undefined();
+        st.sp = 0xFF;
+        st.push_u16(0x1234);
+        assert_eq!(st.ram_get(0x1FF), 0x12);
+        assert_eq!(st.ram_get(0x1FE), 0x34);
+        assert_eq!(st.pop_u16(), 0x1234);
+        assert_eq!(st.sp, 0xFF);
+    }
+
+    #[test]
+    fn test_run_until_brk_errors() {
+        let mut st = State::new_undefined();
+        st.load_program(&[0x4C, 0x00, 0x06], 0x0600);
+        assert_eq!(st.run_until_brk(10), Err(Error::StepLimit { steps: 10 }));
+
+        st.load_program(&[0x4C, 0x00, 0x80], 0x0600);
+        assert_eq!(
+            st.run_until_brk(10),
+            Err(Error::OutOfRam {
+                pc: 0x8000,
+                addr: 0x8000
+            })
+        );
+
+        st.load_program(&[0xEA, 0x02], 0x0600);
+        assert_eq!(
+            st.run_until_brk(10),
+            Err(Error::IllegalOpcode {
+                pc: 0x0601,
+                opcode: 0x02
+            })
+        );
+
+        st.load_program(&[0xEA, 0x00], 0x0600);
+        assert_eq!(st.run_until_brk(1), Ok(1));
+    }
+
+    #[test]
+    fn test_run_until_brk() {
+        let program = crate::asm::assembler::assemble(
+            "
+            .org $0600
+                    ldx #0
+                    lda #0
+                    clc
+            loop:   jsr add_x
+                    inx
+                    cpx #5
+                    bne loop
+                    sta $10
+                    brk
+            add_x:  stx $11
+                    adc $11
+                    rts
+            ",
+        )
+        .unwrap();
+
+        let mut st = State::new_undefined();
+        st.sp = 0xFF;
+        st.load_program(&program, 0x0600);
+        let count = st.run_until_brk(1000).unwrap();
+
+        // 0 + 1 + 2 + 3 + 4
+        assert_eq!(st.ram_get(0x10), 10);
+        assert_eq!(st.x, 5);
+        assert_eq!(st.sp, 0xFF);
+        assert_eq!(st.ram_get(st.pc), 0x00);
+        assert_eq!(count, 3 + 5 * 7 + 1);
+    }
 }
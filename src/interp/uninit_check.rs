@@ -0,0 +1,160 @@
+use std::cell::{Cell, RefCell};
+
+/// Read of a ram byte which was never written since power-on
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct UninitRead {
+    /// Address of the instruction which did the read
+    pub pc: u16,
+    pub addr: u16,
+}
+
+/// Ram bytes written since power-on, and CPU reads of the remaining ones
+/// Each address is reported only once until the next power-on.
+#[derive(Clone, Debug)]
+pub struct UninitCheck {
+    written: Vec<bool>,
+    /// PC of the instruction being executed
+    pc: u16,
+    // reads happen through `&State`, hence the cells
+    reported: Vec<Cell<bool>>,
+    reads: RefCell<Vec<UninitRead>>,
+}
+
+impl UninitCheck {
+    /// Create a check for ram of @size bytes with nothing written yet
+    pub fn new(size: usize) -> UninitCheck {
+        UninitCheck {
+            written: vec![false; size],
+            pc: 0,
+            reported: vec![Cell::new(false); size],
+            reads: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Called when the instruction at @pc starts executing
+    pub fn set_pc(&mut self, pc: u16) {
+        self.pc = pc;
+    }
+
+    pub fn on_write(&mut self, addr: u16) {
+        self.written[addr as usize] = true;
+    }
+
+    pub fn on_read(&self, addr: u16) {
+        let i = addr as usize;
+        if self.written[i] || self.reported[i].replace(true) {
+            return;
+        }
+        self.reads
+            .borrow_mut()
+            .push(UninitRead { pc: self.pc, addr });
+    }
+
+    /// Forget all writes and reported reads, as after power-on
+    pub fn reset(&mut self) {
+        self.written.iter_mut().for_each(|w| *w = false);
+        self.reported.iter().for_each(|r| r.set(false));
+        self.reads.get_mut().clear();
+    }
+
+    /// Return reads of uninitialized bytes reported so far
+    pub fn reads(&self) -> Vec<UninitRead> {
+        self.reads.borrow().clone()
+    }
+
+    /// Return and forget reads of uninitialized bytes reported so far
+    /// Taken addresses stay reported and are not reported again
+    pub fn take_reads(&mut self) -> Vec<UninitRead> {
+        std::mem::take(self.reads.get_mut())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UninitRead;
+    use crate::asm::assembler::assemble;
+    use crate::interp::config::Config;
+    use crate::interp::state::State;
+
+    #[test]
+    fn reports_first_read_once() {
+        let mut st = State::new(&Config {
+            uninit_check: true,
+            ..Config::default()
+        });
+        st.sp = 0xFF;
+        let program = assemble(
+            "
+            .org $0600
+                    lda #1
+                    sta $11
+                    lda $11
+                    lda $10
+                    lda $10
+                    brk
+            ",
+        )
+        .unwrap();
+        st.load_program(&program, 0x0600);
+        st.run_until_brk(1000).unwrap();
+
+        let reads = st.uninit_check_mut().unwrap().take_reads();
+        assert_eq!(
+            reads,
+            vec![UninitRead {
+                pc: 0x0606,
+                addr: 0x10
+            }]
+        );
+        assert_eq!(st.uninit_check().unwrap().reads(), vec![]);
+    }
+
+    #[test]
+    fn stack_reads_after_push() {
+        let mut st = State::new(&Config {
+            uninit_check: true,
+            ..Config::default()
+        });
+        st.sp = 0xFF;
+        st.load_program(&[0x48, 0x68, 0x68, 0x00], 0x0600);
+        st.run_until_brk(1000).unwrap();
+
+        // the second pla pops a byte nobody pushed
+        let reads = st.uninit_check().unwrap().reads();
+        assert_eq!(
+            reads,
+            vec![UninitRead {
+                pc: 0x0602,
+                addr: 0x100
+            }]
+        );
+    }
+
+    #[test]
+    fn host_peeks_are_not_reported() {
+        let mut st = State::new(&Config {
+            uninit_check: true,
+            ..Config::default()
+        });
+        st.ram_get(0x42);
+        st.read_u16_le(0x42);
+        assert!(!st.is_idle_loop());
+        assert_eq!(st.run_until_brk(10), Ok(0));
+        assert_eq!(st.uninit_check_mut().unwrap().take_reads(), vec![]);
+
+        st.cpu_read(0x42);
+        st.cpu_read(0x42);
+        assert_eq!(
+            st.uninit_check_mut().unwrap().take_reads(),
+            vec![UninitRead { pc: 0, addr: 0x42 }]
+        );
+        st.cpu_read(0x42);
+        assert_eq!(st.uninit_check().unwrap().reads(), vec![]);
+    }
+
+    #[test]
+    fn disabled_by_default() {
+        let st = State::new(&Config::default());
+        assert!(st.uninit_check().is_none());
+    }
+}
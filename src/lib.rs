@@ -0,0 +1,17 @@
+pub mod asm;
+pub mod audio;
+pub mod cart;
+pub mod debugger;
+pub mod gamedb;
+pub mod image;
+pub mod input;
+pub mod instruction;
+pub mod interp;
+pub mod palette;
+pub mod patch;
+pub mod ramsearch;
+pub mod ramwatch;
+pub mod savestate;
+pub mod sync;
+pub mod trace;
+pub mod video;
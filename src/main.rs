@@ -1,6 +1,65 @@
-mod instruction;
-mod interp;
+use nesem::asm::assembler::assemble;
+use nesem::debugger::repl::{parse_address, Repl};
+use nesem::debugger::session::Session;
+use nesem::interp::config::Config;
+use nesem::interp::state::{State, RAM_SIZE};
+use std::io::{self, BufRead, Write};
+use std::{env, fs, process};
 
+const USAGE: &str = "usage: nesem <program.asm|program.bin> [load address, $0600 by default]";
+
+/// Print @message and exit with a failure
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
+}
+
+/// Read @path, assembling it if it's a `.asm` or `.s` source
+fn load(path: &str) -> Vec<u8> {
+    let assembly = path.ends_with(".asm") || path.ends_with(".s");
+    let bytes = fs::read(path).unwrap_or_else(|e| fail(&format!("{}: {}", path, e)));
+    if !assembly {
+        return bytes;
+    }
+    let source = String::from_utf8_lossy(&bytes);
+    assemble(&source).unwrap_or_else(|e| fail(&format!("{}:{}: {:?}", path, e.line, e.kind)))
+}
+
+/// Debug a program in ram with commands read from stdin, one per line, see `help`
 fn main() {
-    let ty = instruction::instruction_type::InstructionType::Adc;
+    let args: Vec<String> = env::args().collect();
+    let path = args.get(1).unwrap_or_else(|| fail(USAGE));
+    let at = match args.get(2) {
+        Some(a) => parse_address(a).unwrap_or_else(|e| fail(&e.to_string())),
+        None => 0x0600,
+    };
+    let program = load(path);
+    if at as usize + program.len() > RAM_SIZE {
+        fail("program does not fit into ram");
+    }
+
+    let mut state = State::new(&Config::default());
+    state.sp = 0xFD;
+    state.load_program(&program, at);
+    let mut repl = Repl::new(Session::new(state));
+
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("(nesem) ");
+        let _ = stdout.flush();
+        let line = match lines.next() {
+            Some(Ok(line)) => line,
+            _ => break,
+        };
+        if matches!(line.trim(), "quit" | "q") {
+            break;
+        }
+        match repl.command(&line) {
+            Ok(out) if out.is_empty() => {}
+            Ok(out) => println!("{}", out),
+            Err(e) => println!("error: {}", e),
+        }
+    }
 }
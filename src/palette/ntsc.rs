@@ -0,0 +1,162 @@
+use std::f64::consts::PI;
+
+/// RGB colors indexed by `emphasis << 6 | color`, i.e. 8 rows of 64 colors
+/// Row 0 has no emphasis, bits 0, 1 and 2 of the row emphasize red, green and blue.
+pub type Palette = [[u8; 3]; 512];
+
+/// Knobs of the composite video model used by `generate`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Config {
+    /// Rotation of all hues, in degrees
+    pub hue: f64,
+    /// Multiplier of the chroma signal, 0 gives grayscale
+    pub saturation: f64,
+    /// Gamma of the emulated TV, 2.2 leaves the signal unchanged for sRGB displays
+    pub gamma: f64,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            hue: 0.0,
+            saturation: 1.0,
+            gamma: 2.2,
+        }
+    }
+}
+
+/// Signal voltage of the low and high half of a color wave, for each of the 4 luma levels
+/// See https://www.nesdev.org/wiki/NTSC_video
+const LOW: [f64; 4] = [0.350, 0.518, 0.962, 1.550];
+const HIGH: [f64; 4] = [1.094, 1.506, 1.962, 1.962];
+const BLACK: f64 = 0.518;
+const WHITE: f64 = 1.962;
+/// Emphasized colors attenuate the signal during their third of the color cycle
+const EMPHASIS_ATTENUATION: f64 = 0.746;
+
+/// The PPU outputs 12 samples per cycle of the color subcarrier
+const SAMPLES: usize = 12;
+
+/// Return true if color @hue is in the high half of its wave at @phase
+fn in_phase(hue: usize, phase: usize) -> bool {
+    (hue + phase) % SAMPLES < SAMPLES / 2
+}
+
+/// Voltage the PPU outputs for palette entry @index at @phase of the subcarrier
+fn signal(index: usize, phase: usize) -> f64 {
+    let hue = index & 0x0F;
+    let emphasis = index >> 6;
+    // columns 0xE and 0xF are black regardless of their luma bits
+    let level = if hue > 0x0D { 1 } else { (index >> 4) & 3 };
+    let (low, high) = match hue {
+        0x00 => (HIGH[level], HIGH[level]),
+        0x0D..=0x0F => (LOW[level], LOW[level]),
+        _ => (LOW[level], HIGH[level]),
+    };
+    let mut v = if in_phase(hue, phase) { high } else { low };
+    // red, green and blue emphasis line up with hues 0, 4 and 8
+    let emphasized = (0..3).any(|bit| emphasis & (1 << bit) != 0 && in_phase(bit * 4, phase));
+    // black columns are not attenuated
+    if emphasized && hue < 0x0E {
+        v *= EMPHASIS_ATTENUATION;
+    }
+    v
+}
+
+/// Return a component of the decoded color as a byte, applying @gamma
+fn to_byte(v: f64, gamma: f64) -> u8 {
+    let v = v.clamp(0.0, 1.0).powf(gamma / 2.2);
+    (v * 255.0).round() as u8
+}
+
+/// Compute RGB colors of all palette entries and emphasis settings from @config
+/// The square wave of every entry is decoded by a TV into YIQ, which is converted to RGB.
+pub fn generate(config: &Config) -> Palette {
+    let mut palette = [[0; 3]; 512];
+    for (index, rgb) in palette.iter_mut().enumerate() {
+        let (mut y, mut i, mut q) = (0.0, 0.0, 0.0);
+        for phase in 0..SAMPLES {
+            let v = (signal(index, phase) - BLACK) / (WHITE - BLACK) / SAMPLES as f64;
+            // the color burst does not sit at phase 0, 3.9 samples puts hue 8 at green
+            let angle = PI / 6.0 * (phase as f64 + 3.9) + config.hue.to_radians();
+            y += v;
+            i += v * angle.cos();
+            q += v * angle.sin();
+        }
+        i *= config.saturation;
+        q *= config.saturation;
+        let r = y + 0.946_882 * i + 0.623_557 * q;
+        let g = y - 0.274_788 * i - 0.635_691 * q;
+        let b = y - 1.108_545 * i + 1.709_007 * q;
+        *rgb = [
+            to_byte(r, config.gamma),
+            to_byte(g, config.gamma),
+            to_byte(b, config.gamma),
+        ];
+    }
+    palette
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{generate, Config};
+
+    #[test]
+    fn black_and_white() {
+        let p = generate(&Config::default());
+        assert_eq!(p[0x0F], [0, 0, 0]);
+        assert_eq!(p[0x1D], [0, 0, 0]);
+        assert_eq!(p[0x20], [255, 255, 255]);
+        // gray has no chroma
+        let [r, g, b] = p[0x10];
+        assert!(r == g && g == b);
+    }
+
+    #[test]
+    fn hues() {
+        let p = generate(&Config::default());
+        let dominant = |[r, g, b]: [u8; 3]| {
+            if r > g && r > b {
+                'r'
+            } else if g > r && g > b {
+                'g'
+            } else {
+                'b'
+            }
+        };
+        assert_eq!(dominant(p[0x16]), 'r');
+        assert_eq!(dominant(p[0x1A]), 'g');
+        assert_eq!(dominant(p[0x12]), 'b');
+    }
+
+    #[test]
+    fn emphasis() {
+        let p = generate(&Config::default());
+        let white = p[0x30];
+        let red = p[0x30 | 1 << 6];
+        assert!(red[0] > red[1] && red[0] > red[2]);
+        assert!(red[1] < white[1]);
+        // all three darken everything
+        let all = p[0x30 | 7 << 6];
+        assert!(all.iter().zip(white.iter()).all(|(a, w)| a < w));
+        assert_eq!(p[0x0F | 7 << 6], [0, 0, 0]);
+    }
+
+    #[test]
+    fn knobs() {
+        let gray = generate(&Config {
+            saturation: 0.0,
+            ..Config::default()
+        });
+        let [r, g, b] = gray[0x16];
+        assert!(r == g && g == b);
+
+        let dark = generate(&Config {
+            gamma: 2.8,
+            ..Config::default()
+        });
+        let normal = generate(&Config::default());
+        assert!(dark[0x10][0] < normal[0x10][0]);
+        assert_eq!(dark[0x20], [255, 255, 255]);
+    }
+}
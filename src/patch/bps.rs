@@ -0,0 +1,273 @@
+use super::crc32::crc32;
+use super::error::Error;
+
+const MAGIC: &[u8] = b"BPS1";
+/// source, target and patch CRC32
+const FOOTER_LEN: usize = 12;
+
+pub fn is_bps(patch: &[u8]) -> bool {
+    patch.starts_with(MAGIC)
+}
+
+fn read_u32_le(data: &[u8]) -> u32 {
+    u32::from_le_bytes([data[0], data[1], data[2], data[3]])
+}
+
+/// Reads the actions section of a patch
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn byte(&mut self) -> Result<u8, Error> {
+        let b = *self.data.get(self.pos).ok_or(Error::Truncated)?;
+        self.pos += 1;
+        Ok(b)
+    }
+
+    fn bytes(&mut self, n: usize) -> Result<&'a [u8], Error> {
+        let end = self.pos.checked_add(n).ok_or(Error::Truncated)?;
+        let b = self.data.get(self.pos..end).ok_or(Error::Truncated)?;
+        self.pos = end;
+        Ok(b)
+    }
+
+    /// BPS variable-length integer
+    fn number(&mut self) -> Result<usize, Error> {
+        let mut data: usize = 0;
+        let mut shift: usize = 1;
+        loop {
+            let x = self.byte()?;
+            data = ((x & 0x7F) as usize)
+                .checked_mul(shift)
+                .and_then(|v| data.checked_add(v))
+                .ok_or(Error::OutOfBounds)?;
+            if x & 0x80 > 0 {
+                return Ok(data);
+            }
+            // checked_shl only rejects shifting by more than the width, not losing bits
+            shift = shift.checked_mul(0x80).ok_or(Error::OutOfBounds)?;
+            data = data.checked_add(shift).ok_or(Error::OutOfBounds)?;
+        }
+    }
+
+    /// Signed relative offset used by source and target copy
+    fn offset(&mut self, base: usize) -> Result<usize, Error> {
+        let n = self.number()?;
+        let magnitude = n >> 1;
+        let result = if n & 1 > 0 {
+            base.checked_sub(magnitude)
+        } else {
+            base.checked_add(magnitude)
+        };
+        result.ok_or(Error::OutOfBounds)
+    }
+}
+
+/// Apply BPS @patch to @rom
+/// See https://www.romhacking.net/documents/746/
+/// CRC32 of the source, result and patch are all validated
+pub fn apply(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, Error> {
+    if !is_bps(patch) {
+        return Err(Error::BadHeader);
+    }
+    if patch.len() < MAGIC.len() + FOOTER_LEN {
+        return Err(Error::Truncated);
+    }
+
+    let footer = &patch[patch.len() - FOOTER_LEN..];
+    if crc32(&patch[..patch.len() - 4]) != read_u32_le(&footer[8..]) {
+        return Err(Error::PatchChecksum);
+    }
+
+    let mut r = Reader {
+        data: &patch[..patch.len() - FOOTER_LEN],
+        pos: MAGIC.len(),
+    };
+    let source_size = r.number()?;
+    let target_size = r.number()?;
+    let metadata_size = r.number()?;
+    r.bytes(metadata_size)?;
+
+    if source_size != rom.len() {
+        return Err(Error::SourceSize);
+    }
+    if crc32(rom) != read_u32_le(&footer[0..]) {
+        return Err(Error::SourceChecksum);
+    }
+
+    // target_size comes from the patch, don't let it reserve arbitrary amounts of memory
+    let mut out: Vec<u8> = Vec::with_capacity(target_size.min(rom.len() + patch.len()));
+    let mut source_offset = 0;
+    let mut target_offset = 0;
+    while r.pos < r.data.len() {
+        let data = r.number()?;
+        let length = (data >> 2) + 1;
+        // out never grows past target_size, so this can't underflow
+        if length > target_size - out.len() {
+            return Err(Error::OutOfBounds);
+        }
+
+        match data & 3 {
+            // source read
+            0 => {
+                let start = out.len();
+                let src = rom
+                    .get(start..start.checked_add(length).ok_or(Error::OutOfBounds)?)
+                    .ok_or(Error::OutOfBounds)?;
+                out.extend_from_slice(src);
+            }
+            // target read
+            1 => out.extend_from_slice(r.bytes(length)?),
+            // source copy
+            2 => {
+                source_offset = r.offset(source_offset)?;
+                let end = source_offset
+                    .checked_add(length)
+                    .ok_or(Error::OutOfBounds)?;
+                let src = rom.get(source_offset..end).ok_or(Error::OutOfBounds)?;
+                out.extend_from_slice(src);
+                source_offset = end;
+            }
+            // target copy, may overlap with bytes written by itself
+            _ => {
+                target_offset = r.offset(target_offset)?;
+                for _ in 0..length {
+                    let b = *out.get(target_offset).ok_or(Error::OutOfBounds)?;
+                    out.push(b);
+                    target_offset += 1;
+                }
+            }
+        }
+    }
+
+    if out.len() != target_size {
+        return Err(Error::OutOfBounds);
+    }
+    if crc32(&out) != read_u32_le(&footer[4..]) {
+        return Err(Error::TargetChecksum);
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::apply;
+    use crate::patch::crc32::crc32;
+    use crate::patch::error::Error;
+
+    fn number(out: &mut Vec<u8>, mut n: usize) {
+        loop {
+            let x = (n & 0x7F) as u8;
+            n >>= 7;
+            if n == 0 {
+                out.push(0x80 | x);
+                return;
+            }
+            out.push(x);
+            n -= 1;
+        }
+    }
+
+    fn action(out: &mut Vec<u8>, command: usize, length: usize) {
+        number(out, ((length - 1) << 2) | command);
+    }
+
+    /// Build a patch from raw @header numbers and @actions, with valid checksums
+    fn build_raw(source: &[u8], target: &[u8], header: &[u8], actions: &[u8]) -> Vec<u8> {
+        let mut p = b"BPS1".to_vec();
+        p.extend_from_slice(header);
+        p.extend_from_slice(actions);
+        p.extend_from_slice(&crc32(source).to_le_bytes());
+        p.extend_from_slice(&crc32(target).to_le_bytes());
+        let c = crc32(&p);
+        p.extend_from_slice(&c.to_le_bytes());
+        p
+    }
+
+    /// Build a patch turning @source into @target from @actions
+    fn build(source: &[u8], target: &[u8], actions: &[u8]) -> Vec<u8> {
+        let mut header = Vec::new();
+        number(&mut header, source.len());
+        number(&mut header, target.len());
+        number(&mut header, 0);
+        build_raw(source, target, &header, actions)
+    }
+
+    fn sample() -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+        let source = b"ABCDEFGH".to_vec();
+        let target = b"ABCDxyxyxyGH".to_vec();
+        let mut actions = Vec::new();
+        // "ABCD" from the same offset in source
+        action(&mut actions, 0, 4);
+        // "xy" from the patch
+        action(&mut actions, 1, 2);
+        actions.extend_from_slice(b"xy");
+        // "xyxy" by copying the target onto itself from offset 4
+        action(&mut actions, 3, 4);
+        number(&mut actions, 4 << 1);
+        // "GH" from source offset 6
+        action(&mut actions, 2, 2);
+        number(&mut actions, 6 << 1);
+        let patch = build(&source, &target, &actions);
+        (source, target, patch)
+    }
+
+    #[test]
+    fn all_actions() {
+        let (source, target, patch) = sample();
+        assert_eq!(apply(&source, &patch), Ok(target));
+    }
+
+    #[test]
+    fn wrong_source() {
+        let (_, _, patch) = sample();
+        assert_eq!(apply(b"ABCDEFGX", &patch), Err(Error::SourceChecksum));
+        assert_eq!(apply(b"ABC", &patch), Err(Error::SourceSize));
+    }
+
+    #[test]
+    fn corrupted_patch() {
+        let (source, _, mut patch) = sample();
+        patch[10] ^= 0xFF;
+        assert_eq!(apply(&source, &patch), Err(Error::PatchChecksum));
+    }
+
+    #[test]
+    fn wrong_target_checksum() {
+        let source = b"AB".to_vec();
+        let mut actions = Vec::new();
+        action(&mut actions, 0, 2);
+        let patch = build(&source, b"XY", &actions);
+        assert_eq!(apply(&source, &patch), Err(Error::TargetChecksum));
+    }
+
+    #[test]
+    fn bad_header() {
+        assert_eq!(apply(&[], b"BPS2"), Err(Error::BadHeader));
+    }
+
+    #[test]
+    fn number_overflow() {
+        // 10 bytes without the terminating bit do not fit into 64 bits
+        let mut header = vec![0x7F; 9];
+        header.push(0xFF);
+        let patch = build_raw(b"", b"", &header, &[]);
+        assert_eq!(apply(b"", &patch), Err(Error::OutOfBounds));
+    }
+
+    #[test]
+    fn huge_target_size() {
+        let source = b"AB".to_vec();
+        let mut header = Vec::new();
+        number(&mut header, source.len());
+        number(&mut header, 1 << 62);
+        number(&mut header, 0);
+        let mut actions = Vec::new();
+        action(&mut actions, 0, 2);
+        let patch = build_raw(&source, b"AB", &header, &actions);
+        assert_eq!(apply(&source, &patch), Err(Error::OutOfBounds));
+    }
+}
@@ -0,0 +1,17 @@
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    /// Patch doesn't start with a known magic
+    BadHeader,
+    /// Patch ends in the middle of a record
+    Truncated,
+    /// Patch refers to data outside of the source or target
+    OutOfBounds,
+    /// Source size stored in the patch differs from the actual source
+    SourceSize,
+    /// CRC32 of the source differs from the one stored in the patch
+    SourceChecksum,
+    /// CRC32 of the patched result differs from the one stored in the patch
+    TargetChecksum,
+    /// CRC32 of the patch itself is wrong, the patch is corrupted
+    PatchChecksum,
+}
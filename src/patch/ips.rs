@@ -0,0 +1,116 @@
+use super::error::Error;
+
+const MAGIC: &[u8] = b"PATCH";
+const EOF_MARKER: &[u8] = b"EOF";
+
+pub fn is_ips(patch: &[u8]) -> bool {
+    patch.starts_with(MAGIC)
+}
+
+/// Take @n bytes from the start of @data, advancing it
+fn take<'a>(data: &mut &'a [u8], n: usize) -> Result<&'a [u8], Error> {
+    if data.len() < n {
+        return Err(Error::Truncated);
+    }
+    let (head, tail) = data.split_at(n);
+    *data = tail;
+    Ok(head)
+}
+
+fn be(bytes: &[u8]) -> usize {
+    bytes.iter().fold(0, |acc, b| (acc << 8) | *b as usize)
+}
+
+/// Apply IPS @patch to @rom
+/// See https://zerosoft.zophar.net/ips.php
+/// Records past the end of @rom grow it, an optional length after `EOF` truncates it
+pub fn apply(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, Error> {
+    if !is_ips(patch) {
+        return Err(Error::BadHeader);
+    }
+
+    let mut out = rom.to_vec();
+    let mut rest = &patch[MAGIC.len()..];
+    loop {
+        if rest.starts_with(EOF_MARKER) && (rest.len() == 3 || rest.len() == 6) {
+            break;
+        }
+
+        let offset = be(take(&mut rest, 3)?);
+        let size = be(take(&mut rest, 2)?);
+        let (size, data) = if size == 0 {
+            // run-length encoded record
+            let size = be(take(&mut rest, 2)?);
+            let value = take(&mut rest, 1)?[0];
+            (size, vec![value; size])
+        } else {
+            (size, take(&mut rest, size)?.to_vec())
+        };
+
+        if out.len() < offset + size {
+            out.resize(offset + size, 0);
+        }
+        out[offset..offset + size].copy_from_slice(&data);
+    }
+
+    let mut rest = &rest[EOF_MARKER.len()..];
+    if !rest.is_empty() {
+        let len = be(take(&mut rest, 3)?);
+        out.truncate(len);
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::apply;
+    use crate::patch::error::Error;
+
+    #[test]
+    fn plain_record() {
+        let patch = b"PATCH\x00\x00\x01\x00\x02\xAA\xBBEOF";
+        assert_eq!(apply(&[0, 0, 0, 0], patch), Ok(vec![0, 0xAA, 0xBB, 0]));
+    }
+
+    #[test]
+    fn rle_record() {
+        let patch = b"PATCH\x00\x00\x01\x00\x00\x00\x03\x11EOF";
+        assert_eq!(apply(&[0; 5], patch), Ok(vec![0, 0x11, 0x11, 0x11, 0]));
+    }
+
+    #[test]
+    fn grows_rom() {
+        let patch = b"PATCH\x00\x00\x03\x00\x02\x01\x02EOF";
+        assert_eq!(apply(&[9, 9], patch), Ok(vec![9, 9, 0, 1, 2]));
+    }
+
+    #[test]
+    fn truncates_rom() {
+        let patch = b"PATCHEOF\x00\x00\x02";
+        assert_eq!(apply(&[1, 2, 3, 4], patch), Ok(vec![1, 2]));
+    }
+
+    #[test]
+    fn record_at_eof_offset() {
+        // offset 0x454F46 spells "EOF" but is followed by more data
+        let mut rom = vec![0; 0x454F48];
+        rom[0x454F46] = 1;
+        let patch = b"PATCHEOF\x00\x01\xFFEOF";
+        let out = apply(&rom, patch).unwrap();
+        assert_eq!(out[0x454F46], 0xFF);
+    }
+
+    #[test]
+    fn bad_header() {
+        assert_eq!(apply(&[], b"PATCX"), Err(Error::BadHeader));
+    }
+
+    #[test]
+    fn truncated() {
+        assert_eq!(
+            apply(&[0; 4], b"PATCH\x00\x00\x01\x00\x05\xAA"),
+            Err(Error::Truncated)
+        );
+    }
+}
@@ -0,0 +1,6 @@
+pub mod bps;
+pub mod crc32;
+pub mod error;
+pub mod ips;
+pub mod patcher;
+pub mod soft_patch;
@@ -0,0 +1,30 @@
+use super::error::Error;
+use super::{bps, ips};
+
+/// Apply @patch to @rom, detecting the patch format from its header
+pub fn apply(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, Error> {
+    if ips::is_ips(patch) {
+        ips::apply(rom, patch)
+    } else if bps::is_bps(patch) {
+        bps::apply(rom, patch)
+    } else {
+        Err(Error::BadHeader)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::apply;
+    use crate::patch::error::Error;
+
+    #[test]
+    fn detects_ips() {
+        let patch = b"PATCH\x00\x00\x00\x00\x01\xFFEOF";
+        assert_eq!(apply(&[0], patch), Ok(vec![0xFF]));
+    }
+
+    #[test]
+    fn unknown_format() {
+        assert_eq!(apply(&[0], b"UPS1"), Err(Error::BadHeader));
+    }
+}
@@ -0,0 +1,103 @@
+use super::error::Error;
+use super::patcher;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Patch extensions looked for next to a ROM, in order of preference
+/// BPS goes first since it validates checksums
+const EXTENSIONS: [&str; 2] = ["bps", "ips"];
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    Patch(PathBuf, Error),
+}
+
+impl From<io::Error> for LoadError {
+    fn from(e: io::Error) -> LoadError {
+        LoadError::Io(e)
+    }
+}
+
+/// Return path of a patch with the same name as @rom_path, e.g. `game.ips` for `game.nes`
+pub fn find_soft_patch(rom_path: &Path) -> Option<PathBuf> {
+    EXTENSIONS
+        .iter()
+        .map(|ext| rom_path.with_extension(ext))
+        .find(|p| p.is_file())
+}
+
+/// Read ROM at @rom_path
+/// If @soft_patch is set and a patch is found by `find_soft_patch`, it's applied to the ROM
+pub fn load_rom(rom_path: &Path, soft_patch: bool) -> Result<Vec<u8>, LoadError> {
+    let rom = fs::read(rom_path)?;
+    if !soft_patch {
+        return Ok(rom);
+    }
+
+    match find_soft_patch(rom_path) {
+        Some(patch_path) => {
+            let patch = fs::read(&patch_path)?;
+            patcher::apply(&rom, &patch).map_err(|e| LoadError::Patch(patch_path, e))
+        }
+        None => Ok(rom),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_soft_patch, load_rom, LoadError};
+    use crate::patch::error::Error;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("nesem-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn applies_ips_next_to_rom() {
+        let dir = temp_dir("soft-patch-ips");
+        let rom = dir.join("game.nes");
+        fs::write(&rom, [0, 0]).unwrap();
+        fs::write(dir.join("game.ips"), b"PATCH\x00\x00\x01\x00\x01\x07EOF").unwrap();
+
+        assert_eq!(find_soft_patch(&rom), Some(dir.join("game.ips")));
+        assert_eq!(load_rom(&rom, true).unwrap(), vec![0, 7]);
+        assert_eq!(load_rom(&rom, false).unwrap(), vec![0, 0]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn no_patch() {
+        let dir = temp_dir("soft-patch-none");
+        let rom = dir.join("game.nes");
+        fs::write(&rom, [1, 2]).unwrap();
+
+        assert_eq!(find_soft_patch(&rom), None);
+        assert_eq!(load_rom(&rom, true).unwrap(), vec![1, 2]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn broken_patch() {
+        let dir = temp_dir("soft-patch-broken");
+        let rom = dir.join("game.nes");
+        fs::write(&rom, [1, 2]).unwrap();
+        fs::write(dir.join("game.bps"), b"BPS1").unwrap();
+
+        match load_rom(&rom, true) {
+            Err(LoadError::Patch(path, Error::Truncated)) => {
+                assert_eq!(path, dir.join("game.bps"))
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
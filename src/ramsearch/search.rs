@@ -0,0 +1,152 @@
+use super::value_type::ValueType;
+
+/// Condition a candidate address must satisfy to stay in the search
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Filter {
+    EqualTo(i64),
+    NotEqualTo(i64),
+    GreaterThan(i64),
+    LessThan(i64),
+    GreaterThanPrevious,
+    LessThanPrevious,
+    Changed,
+    Unchanged,
+    /// `current - previous == n`
+    ChangedBy(i64),
+}
+
+impl Filter {
+    fn matches(self, previous: i64, current: i64) -> bool {
+        match self {
+            Filter::EqualTo(n) => current == n,
+            Filter::NotEqualTo(n) => current != n,
+            Filter::GreaterThan(n) => current > n,
+            Filter::LessThan(n) => current < n,
+            Filter::GreaterThanPrevious => current > previous,
+            Filter::LessThanPrevious => current < previous,
+            Filter::Changed => current != previous,
+            Filter::Unchanged => current == previous,
+            Filter::ChangedBy(n) => current - previous == n,
+        }
+    }
+}
+
+/// Iteratively narrows down addresses in RAM by comparing snapshots across frames
+///
+/// Typical use is looking for a counter in a game:
+/// start a search, play until the counter changes, `apply(Filter::Changed, ..)`, repeat.
+pub struct Search {
+    value_type: ValueType,
+    candidates: Vec<usize>,
+    /// RAM at the time of the last filter
+    previous: Vec<u8>,
+}
+
+impl Search {
+    /// Start a search with every address of @ram as a candidate
+    pub fn new(value_type: ValueType, ram: &[u8]) -> Search {
+        let count = (ram.len() + 1).saturating_sub(value_type.size());
+        Search {
+            value_type,
+            candidates: (0..count).collect(),
+            previous: ram.to_vec(),
+        }
+    }
+
+    /// Keep only candidates which satisfy @filter when comparing the previous snapshot with @ram
+    /// @ram becomes the previous snapshot for the next filter
+    pub fn apply(&mut self, filter: Filter, ram: &[u8]) {
+        let value_type = self.value_type;
+        let previous = &self.previous;
+        self.candidates.retain(|addr| {
+            match (
+                value_type.read(previous, *addr),
+                value_type.read(ram, *addr),
+            ) {
+                (Some(p), Some(c)) => filter.matches(p, c),
+                _ => false,
+            }
+        });
+        self.previous = ram.to_vec();
+    }
+
+    pub fn candidates(&self) -> &[usize] {
+        &self.candidates
+    }
+
+    /// Return value of @addr in the last snapshot
+    pub fn value(&self, addr: usize) -> Option<i64> {
+        self.value_type.read(&self.previous, addr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Filter, Search};
+    use crate::interp::state::State;
+    use crate::ramsearch::value_type::ValueType;
+
+    #[test]
+    fn find_counter() {
+        let mut st = State::new_undefined();
+        st.ram_set(0x30, 3);
+        st.ram_set(0x31, 3);
+        let mut search = Search::new(ValueType::U8, st.ram());
+        assert_eq!(search.candidates().len(), 0x800);
+
+        search.apply(Filter::EqualTo(3), st.ram());
+        assert_eq!(search.candidates(), &[0x30, 0x31]);
+
+        st.ram_set(0x30, 2);
+        search.apply(Filter::ChangedBy(-1), st.ram());
+        assert_eq!(search.candidates(), &[0x30]);
+        assert_eq!(search.value(0x30), Some(2));
+    }
+
+    #[test]
+    fn compare_with_previous() {
+        let mut ram = vec![5, 5, 5, 5];
+        let mut search = Search::new(ValueType::U8, &ram);
+        ram[0] = 6;
+        ram[1] = 4;
+        search.apply(Filter::Changed, &ram);
+        assert_eq!(search.candidates(), &[0, 1]);
+
+        ram[0] = 7;
+        ram[1] = 3;
+        search.apply(Filter::GreaterThanPrevious, &ram);
+        assert_eq!(search.candidates(), &[0]);
+    }
+
+    #[test]
+    fn unchanged() {
+        let mut ram = vec![1, 2, 3];
+        let mut search = Search::new(ValueType::U8, &ram);
+        ram[2] = 0;
+        search.apply(Filter::Unchanged, &ram);
+        assert_eq!(search.candidates(), &[0, 1]);
+    }
+
+    #[test]
+    fn wide_values() {
+        let mut ram = vec![0x00, 0x01, 0x00];
+        let mut search = Search::new(ValueType::U16, &ram);
+        assert_eq!(search.candidates(), &[0, 1]);
+
+        search.apply(Filter::EqualTo(0x100), &ram);
+        assert_eq!(search.candidates(), &[0]);
+
+        ram[0] = 0xFF;
+        search.apply(Filter::ChangedBy(0xFF), &ram);
+        assert_eq!(search.candidates(), &[0]);
+    }
+
+    #[test]
+    fn signed_values() {
+        let mut ram = vec![0x01, 0x01];
+        let mut search = Search::new(ValueType::I8, &ram);
+        ram[0] = 0xFF;
+        search.apply(Filter::LessThan(0), &ram);
+        assert_eq!(search.candidates(), &[0]);
+    }
+}
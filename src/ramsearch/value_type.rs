@@ -0,0 +1,93 @@
+/// How to interpret bytes at an address
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ValueType {
+    U8,
+    I8,
+    /// little-endian
+    U16,
+    /// little-endian
+    I16,
+    /// two decimal digits packed in one byte, e.g. 0x42 == 42
+    Bcd,
+}
+
+impl ValueType {
+    /// Number of bytes occupied by the value
+    pub fn size(self) -> usize {
+        match self {
+            ValueType::U8 | ValueType::I8 | ValueType::Bcd => 1,
+            ValueType::U16 | ValueType::I16 => 2,
+        }
+    }
+
+    /// Read value at @addr in @memory
+    /// Return None if the value doesn't fit into @memory, or a BCD nibble is above 9
+    pub fn read(self, memory: &[u8], addr: usize) -> Option<i64> {
+        let bytes = memory.get(addr..addr + self.size())?;
+        let value = match self {
+            ValueType::U8 => bytes[0] as i64,
+            ValueType::I8 => bytes[0] as i8 as i64,
+            ValueType::U16 => u16::from_le_bytes([bytes[0], bytes[1]]) as i64,
+            ValueType::I16 => i16::from_le_bytes([bytes[0], bytes[1]]) as i64,
+            ValueType::Bcd => {
+                let (hi, lo) = (bytes[0] >> 4, bytes[0] & 0xF);
+                if hi > 9 || lo > 9 {
+                    return None;
+                }
+                (hi * 10 + lo) as i64
+            }
+        };
+        Some(value)
+    }
+
+    /// Return bytes representing @value, truncated to `size()` bytes
+    pub fn encode(self, value: i64) -> Vec<u8> {
+        match self {
+            ValueType::U8 | ValueType::I8 => vec![value as u8],
+            ValueType::U16 | ValueType::I16 => (value as u16).to_le_bytes().to_vec(),
+            ValueType::Bcd => {
+                let v = value.rem_euclid(100) as u8;
+                vec![(v / 10) << 4 | (v % 10)]
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ValueType;
+
+    #[test]
+    fn read_values() {
+        let mem = [0xFE, 0xFF, 0x42];
+        assert_eq!(ValueType::U8.read(&mem, 0), Some(0xFE));
+        assert_eq!(ValueType::I8.read(&mem, 0), Some(-2));
+        assert_eq!(ValueType::U16.read(&mem, 0), Some(0xFFFE));
+        assert_eq!(ValueType::I16.read(&mem, 0), Some(-2));
+        assert_eq!(ValueType::Bcd.read(&mem, 2), Some(42));
+    }
+
+    #[test]
+    fn read_invalid_bcd() {
+        let mem = [0x4A, 0xA4, 0x99];
+        assert_eq!(ValueType::Bcd.read(&mem, 0), None);
+        assert_eq!(ValueType::Bcd.read(&mem, 1), None);
+        assert_eq!(ValueType::Bcd.read(&mem, 2), Some(99));
+    }
+
+    #[test]
+    fn read_out_of_bounds() {
+        let mem = [0x00, 0x01];
+        assert_eq!(ValueType::U16.read(&mem, 1), None);
+        assert_eq!(ValueType::U8.read(&mem, 2), None);
+    }
+
+    #[test]
+    fn encode_values() {
+        assert_eq!(ValueType::U8.encode(0x1FE), vec![0xFE]);
+        assert_eq!(ValueType::I8.encode(-2), vec![0xFE]);
+        assert_eq!(ValueType::U16.encode(0x1234), vec![0x34, 0x12]);
+        assert_eq!(ValueType::I16.encode(-2), vec![0xFE, 0xFF]);
+        assert_eq!(ValueType::Bcd.encode(42), vec![0x42]);
+    }
+}
@@ -0,0 +1,208 @@
+use crate::interp::state::{State, RAM_SIZE};
+use crate::ramsearch::value_type::ValueType;
+
+/// How to show a watched value
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum DisplayFormat {
+    Decimal,
+    Hex,
+    Binary,
+}
+
+impl DisplayFormat {
+    /// Format @value which occupies @size bytes
+    fn format(self, value: i64, size: usize) -> String {
+        let bits = size * 8;
+        let raw = (value as u64) & ((1 << bits) - 1);
+        match self {
+            DisplayFormat::Decimal => value.to_string(),
+            DisplayFormat::Hex => format!("{:0width$X}", raw, width = size * 2),
+            DisplayFormat::Binary => format!("{:0width$b}", raw, width = bits),
+        }
+    }
+}
+
+pub struct Watch {
+    pub label: String,
+    pub addr: u16,
+    pub value_type: ValueType,
+    pub format: DisplayFormat,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    /// Watch at @addr of @size bytes does not fit into ram
+    OutOfRam { addr: u16, size: usize },
+}
+
+/// Value of a watch sampled at the end of a frame
+#[derive(Debug, PartialEq)]
+pub struct Sample {
+    /// None if the bytes are not a value of the watched type, e.g. BCD with a nibble above 9
+    pub value: Option<i64>,
+    /// Formatted value, `--` for no value
+    pub text: String,
+    pub frozen: bool,
+}
+
+/// List of ram addresses sampled every frame
+#[derive(Default)]
+pub struct WatchList {
+    watches: Vec<Watch>,
+}
+
+impl WatchList {
+    pub fn new() -> WatchList {
+        WatchList {
+            watches: Vec::new(),
+        }
+    }
+
+    /// Add @watch to the end of the list, all of its bytes must be in ram
+    pub fn add(&mut self, watch: Watch) -> Result<(), Error> {
+        let size = watch.value_type.size();
+        if watch.addr as usize + size > RAM_SIZE {
+            return Err(Error::OutOfRam {
+                addr: watch.addr,
+                size,
+            });
+        }
+        self.watches.push(watch);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, index: usize) -> Watch {
+        self.watches.remove(index)
+    }
+
+    pub fn watches(&self) -> &[Watch] {
+        &self.watches
+    }
+
+    /// Read all watches from @state, in the order they were added
+    pub fn sample(&self, state: &State) -> Vec<Sample> {
+        self.watches
+            .iter()
+            .map(|w| {
+                assert!(
+                    w.addr as usize + w.value_type.size() <= state.ram().len(),
+                    "sample: watch is checked to be in ram by add"
+                );
+                let value = w.value_type.read(state.ram(), w.addr as usize);
+                Sample {
+                    value,
+                    text: value.map_or_else(
+                        || String::from("--"),
+                        |v| w.format.format(v, w.value_type.size()),
+                    ),
+                    frozen: state.is_frozen(w.addr),
+                }
+            })
+            .collect()
+    }
+
+    /// Keep watch @index at @value, reapplied after every write
+    pub fn freeze(&self, index: usize, value: i64, state: &mut State) {
+        let w = &self.watches[index];
+        for (i, byte) in w.value_type.encode(value).into_iter().enumerate() {
+            state.freeze(w.addr.wrapping_add(i as u16), byte);
+        }
+    }
+
+    pub fn unfreeze(&self, index: usize, state: &mut State) {
+        let w = &self.watches[index];
+        for i in 0..w.value_type.size() {
+            state.unfreeze(w.addr.wrapping_add(i as u16));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DisplayFormat, Error, Watch, WatchList};
+    use crate::interp::state::State;
+    use crate::ramsearch::value_type::ValueType;
+
+    fn watch(addr: u16, value_type: ValueType, format: DisplayFormat) -> Watch {
+        Watch {
+            label: String::from("test"),
+            addr,
+            value_type,
+            format,
+        }
+    }
+
+    #[test]
+    fn sample_formats() {
+        let mut st = State::new_undefined();
+        st.ram_set(0x10, 0xFE);
+        st.ram_set(0x11, 0x01);
+        let mut list = WatchList::new();
+        list.add(watch(0x10, ValueType::I8, DisplayFormat::Decimal))
+            .unwrap();
+        list.add(watch(0x10, ValueType::U16, DisplayFormat::Hex))
+            .unwrap();
+        list.add(watch(0x11, ValueType::U8, DisplayFormat::Binary))
+            .unwrap();
+
+        let samples = list.sample(&st);
+        assert_eq!(samples[0].text, "-2");
+        assert_eq!(samples[1].text, "01FE");
+        assert_eq!(samples[2].text, "00000001");
+        assert!(samples.iter().all(|s| !s.frozen));
+    }
+
+    #[test]
+    fn sample_invalid_bcd() {
+        let mut st = State::new_undefined();
+        st.ram_set(0x10, 0x4A);
+        let mut list = WatchList::new();
+        list.add(watch(0x10, ValueType::Bcd, DisplayFormat::Decimal))
+            .unwrap();
+        let samples = list.sample(&st);
+        assert_eq!(samples[0].value, None);
+        assert_eq!(samples[0].text, "--");
+    }
+
+    #[test]
+    fn freeze_reapplies_after_write() {
+        let mut st = State::new_undefined();
+        let mut list = WatchList::new();
+        list.add(watch(0x20, ValueType::U16, DisplayFormat::Decimal))
+            .unwrap();
+
+        list.freeze(0, 999, &mut st);
+        st.ram_set(0x20, 0);
+        st.ram_set(0x21, 0);
+        let samples = list.sample(&st);
+        assert_eq!(samples[0].value, Some(999));
+        assert!(samples[0].frozen);
+
+        list.unfreeze(0, &mut st);
+        st.ram_set(0x21, 0);
+        assert_eq!(list.sample(&st)[0].value, Some(999 & 0xFF));
+    }
+
+    #[test]
+    fn add_outside_ram() {
+        let mut list = WatchList::new();
+        assert_eq!(
+            list.add(watch(0x6000, ValueType::U8, DisplayFormat::Hex)),
+            Err(Error::OutOfRam {
+                addr: 0x6000,
+                size: 1
+            })
+        );
+        assert_eq!(
+            list.add(watch(0x7FF, ValueType::U16, DisplayFormat::Hex)),
+            Err(Error::OutOfRam {
+                addr: 0x7FF,
+                size: 2
+            })
+        );
+        assert!(list
+            .add(watch(0x7FF, ValueType::U8, DisplayFormat::Hex))
+            .is_ok());
+        assert_eq!(list.watches().len(), 1);
+    }
+}
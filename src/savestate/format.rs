@@ -0,0 +1,231 @@
+use crate::interp::rng::Rng;
+use crate::interp::state::State;
+
+const MAGIC: &[u8; 4] = b"NESM";
+
+/// Save state is a header followed by a sequence of chunks:
+/// `"NESM" | version: u16 LE | (tag: [u8; 4] | length: u32 LE | payload)*`
+///
+/// Compatibility policy:
+/// - adding a component means adding a chunk with a new tag, the version stays the same
+/// - chunks with unknown tags are skipped, so older nesem versions load newer states
+/// - missing chunks leave the component as in `State::new_undefined()`,
+///   so newer nesem versions load older states
+/// - changing the layout of an existing chunk requires a new tag
+/// - the version is bumped only when the header or chunk framing changes
+pub const VERSION: u16 = 1;
+
+const CPU_TAG: &[u8; 4] = b"CPU ";
+const RAM_TAG: &[u8; 4] = b"RAM ";
+const RNG_TAG: &[u8; 4] = b"RNG ";
+
+/// pc (2 bytes), sp, psw, accumulator, x, y
+const CPU_CHUNK_LEN: usize = 7;
+
+#[derive(Debug, PartialEq)]
+pub enum LoadError {
+    /// Data doesn't start with the save-state magic
+    BadMagic,
+    /// State was saved by a nesem version with incompatible framing
+    UnsupportedVersion(u16),
+    /// Data ends in the middle of the header or a chunk
+    Truncated,
+    /// A known chunk has unexpected length
+    BadChunkLength([u8; 4]),
+}
+
+fn write_chunk(out: &mut Vec<u8>, tag: &[u8; 4], payload: &[u8]) {
+    out.extend_from_slice(tag);
+    out.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    out.extend_from_slice(payload);
+}
+
+/// Serialize @state into a save state
+pub fn save(state: &State) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&VERSION.to_le_bytes());
+
+    let pc = state.pc.to_le_bytes();
+    let cpu = [
+        pc[0],
+        pc[1],
+        state.sp,
+        state.psw,
+        state.accumulator,
+        state.x,
+        state.y,
+    ];
+    write_chunk(&mut out, CPU_TAG, &cpu);
+    write_chunk(&mut out, RAM_TAG, state.ram());
+    write_chunk(&mut out, RNG_TAG, &state.rng().state().to_le_bytes());
+
+    out
+}
+
+struct Chunk<'a> {
+    tag: [u8; 4],
+    payload: &'a [u8],
+}
+
+/// Split @data into the first chunk and the rest
+fn read_chunk(data: &[u8]) -> Result<(Chunk<'_>, &[u8]), LoadError> {
+    if data.len() < 8 {
+        return Err(LoadError::Truncated);
+    }
+
+    let mut tag = [0; 4];
+    tag.copy_from_slice(&data[..4]);
+    let mut len = [0; 4];
+    len.copy_from_slice(&data[4..8]);
+    let len = u32::from_le_bytes(len) as usize;
+
+    let data = &data[8..];
+    if data.len() < len {
+        return Err(LoadError::Truncated);
+    }
+    let chunk = Chunk {
+        tag,
+        payload: &data[..len],
+    };
+    Ok((chunk, &data[len..]))
+}
+
+/// Deserialize a save state produced by `save`
+pub fn load(data: &[u8]) -> Result<State, LoadError> {
+    if data.len() < 6 {
+        return Err(LoadError::Truncated);
+    }
+    if &data[..4] != MAGIC {
+        return Err(LoadError::BadMagic);
+    }
+    let version = u16::from_le_bytes([data[4], data[5]]);
+    if version != VERSION {
+        return Err(LoadError::UnsupportedVersion(version));
+    }
+
+    let mut state = State::new_undefined();
+    let mut rest = &data[6..];
+    while !rest.is_empty() {
+        let (Chunk { tag, payload }, next) = read_chunk(rest)?;
+        rest = next;
+
+        match &tag {
+            CPU_TAG => {
+                if payload.len() != CPU_CHUNK_LEN {
+                    return Err(LoadError::BadChunkLength(tag));
+                }
+                state.pc = u16::from_le_bytes([payload[0], payload[1]]);
+                state.sp = payload[2];
+                state.psw = payload[3];
+                state.accumulator = payload[4];
+                state.x = payload[5];
+                state.y = payload[6];
+            }
+            RAM_TAG => {
+                if payload.len() != state.ram().len() {
+                    return Err(LoadError::BadChunkLength(tag));
+                }
+                state.ram_mut().copy_from_slice(payload);
+            }
+            RNG_TAG => {
+                if payload.len() != 8 {
+                    return Err(LoadError::BadChunkLength(tag));
+                }
+                let mut seed = [0; 8];
+                seed.copy_from_slice(payload);
+                state.set_rng(Rng::new(u64::from_le_bytes(seed)));
+            }
+            // chunk from a newer version, skip it
+            _ => {}
+        }
+    }
+
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{load, save, write_chunk, LoadError, MAGIC, VERSION};
+    use crate::interp::config::Config;
+    use crate::interp::state::{PowerOnRamPattern, State};
+
+    fn sample_state() -> State {
+        let mut st = State::new(&Config {
+            seed: 7,
+            ram_pattern: PowerOnRamPattern::Random,
+            ..Config::default()
+        });
+        st.pc = 0xC123;
+        st.sp = 0xFD;
+        st.accumulator = 1;
+        st.x = 2;
+        st.y = 3;
+        st.set_carry(true);
+        st
+    }
+
+    #[test]
+    fn round_trip() {
+        let st = sample_state();
+        let loaded = load(&save(&st)).unwrap();
+        assert_eq!(loaded.fingerprint(), st.fingerprint());
+        assert_eq!(loaded.rng(), st.rng());
+    }
+
+    #[test]
+    fn unknown_chunks_are_skipped() {
+        let st = sample_state();
+        let mut data = save(&st);
+        write_chunk(&mut data, b"ZZZZ", &[1, 2, 3]);
+        let loaded = load(&data).unwrap();
+        assert_eq!(loaded.fingerprint(), st.fingerprint());
+    }
+
+    #[test]
+    fn missing_chunks_keep_defaults() {
+        let mut data = Vec::new();
+        data.extend_from_slice(MAGIC);
+        data.extend_from_slice(&VERSION.to_le_bytes());
+        write_chunk(&mut data, b"CPU ", &[0x00, 0x80, 0xFD, 0x24, 1, 2, 3]);
+
+        let loaded = load(&data).unwrap();
+        assert_eq!(loaded.pc, 0x8000);
+        assert_eq!(loaded.y, 3);
+        assert!(loaded.ram().iter().all(|b| *b == 0));
+    }
+
+    #[test]
+    fn bad_magic() {
+        assert_eq!(load(b"NOPE\x01\x00").err(), Some(LoadError::BadMagic));
+    }
+
+    #[test]
+    fn unsupported_version() {
+        let mut data = save(&sample_state());
+        data[4] = 0xFF;
+        assert_eq!(
+            load(&data).err(),
+            Some(LoadError::UnsupportedVersion(0x00FF))
+        );
+    }
+
+    #[test]
+    fn truncated() {
+        let data = save(&sample_state());
+        assert_eq!(
+            load(&data[..data.len() - 1]).err(),
+            Some(LoadError::Truncated)
+        );
+        assert_eq!(load(&data[..3]).err(), Some(LoadError::Truncated));
+    }
+
+    #[test]
+    fn bad_chunk_length() {
+        let mut data = Vec::new();
+        data.extend_from_slice(MAGIC);
+        data.extend_from_slice(&VERSION.to_le_bytes());
+        write_chunk(&mut data, b"RAM ", &[0; 16]);
+        assert_eq!(load(&data).err(), Some(LoadError::BadChunkLength(*b"RAM ")));
+    }
+}
@@ -0,0 +1,249 @@
+use super::format::{load, save, LoadError};
+use crate::interp::state::State;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug)]
+pub enum Error {
+    /// Slot number is out of range
+    InvalidSlot(usize),
+    /// Nothing has been saved there yet
+    Empty,
+    Io(io::Error),
+    Load(LoadError),
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Error {
+        Error::Io(e)
+    }
+}
+
+impl From<LoadError> for Error {
+    fn from(e: LoadError) -> Error {
+        Error::Load(e)
+    }
+}
+
+/// Where a save state is kept
+#[derive(Copy, Clone)]
+enum Location {
+    Slot(usize),
+    Autosave,
+}
+
+/// Keeps numbered save slots, a quick state and periodic autosaves
+///
+/// Slots and the autosave are either kept in memory or written to files in a directory.
+/// The quick state is always kept in memory.
+pub struct Manager {
+    slots: Vec<Option<Vec<u8>>>,
+    autosave: Option<Vec<u8>>,
+    quick: Option<Vec<u8>>,
+    /// Directory with slot files, `None` if slots are kept in memory
+    directory: Option<PathBuf>,
+    /// Number of frames between autosaves, `None` disables autosave
+    autosave_interval: Option<u32>,
+    frames_since_autosave: u32,
+}
+
+impl Manager {
+    /// Create a manager with @slot_count slots kept in memory
+    pub fn new(slot_count: usize) -> Manager {
+        Manager {
+            slots: vec![None; slot_count],
+            autosave: None,
+            quick: None,
+            directory: None,
+            autosave_interval: None,
+            frames_since_autosave: 0,
+        }
+    }
+
+    /// Create a manager with @slot_count slots stored as files in @directory
+    pub fn file_backed(slot_count: usize, directory: PathBuf) -> Manager {
+        Manager {
+            directory: Some(directory),
+            ..Manager::new(slot_count)
+        }
+    }
+
+    pub fn slot_count(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Autosave every @interval frames, `None` disables autosave
+    pub fn set_autosave_interval(&mut self, interval: Option<u32>) {
+        self.autosave_interval = interval;
+        self.frames_since_autosave = 0;
+    }
+
+    fn file_path(directory: &Path, location: Location) -> PathBuf {
+        match location {
+            Location::Slot(n) => directory.join(format!("slot{}.state", n)),
+            Location::Autosave => directory.join("autosave.state"),
+        }
+    }
+
+    fn check_slot(&self, slot: usize) -> Result<(), Error> {
+        if slot < self.slots.len() {
+            Ok(())
+        } else {
+            Err(Error::InvalidSlot(slot))
+        }
+    }
+
+    fn store(&mut self, location: Location, data: Vec<u8>) -> Result<(), Error> {
+        if let Some(directory) = &self.directory {
+            // write to a temporary file first so a crash never leaves a half-written state
+            let path = Manager::file_path(directory, location);
+            let tmp = path.with_extension("state.tmp");
+            fs::create_dir_all(directory)?;
+            fs::write(&tmp, &data)?;
+            fs::rename(&tmp, &path)?;
+            return Ok(());
+        }
+
+        match location {
+            Location::Slot(n) => self.slots[n] = Some(data),
+            Location::Autosave => self.autosave = Some(data),
+        };
+        Ok(())
+    }
+
+    fn fetch(&self, location: Location) -> Result<State, Error> {
+        if let Some(directory) = &self.directory {
+            let data = match fs::read(Manager::file_path(directory, location)) {
+                Ok(data) => data,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => return Err(Error::Empty),
+                Err(e) => return Err(Error::Io(e)),
+            };
+            return Ok(load(&data)?);
+        }
+
+        let data = match location {
+            Location::Slot(n) => &self.slots[n],
+            Location::Autosave => &self.autosave,
+        };
+        match data {
+            Some(data) => Ok(load(data)?),
+            None => Err(Error::Empty),
+        }
+    }
+
+    pub fn save_slot(&mut self, slot: usize, state: &State) -> Result<(), Error> {
+        self.check_slot(slot)?;
+        self.store(Location::Slot(slot), save(state))
+    }
+
+    pub fn load_slot(&self, slot: usize) -> Result<State, Error> {
+        self.check_slot(slot)?;
+        self.fetch(Location::Slot(slot))
+    }
+
+    pub fn quick_save(&mut self, state: &State) {
+        self.quick = Some(save(state));
+    }
+
+    pub fn quick_load(&self) -> Result<State, Error> {
+        match &self.quick {
+            Some(data) => Ok(load(data)?),
+            None => Err(Error::Empty),
+        }
+    }
+
+    /// Notify the manager that a frame has finished with @state
+    /// Autosaves if the autosave interval has elapsed
+    pub fn on_frame(&mut self, state: &State) -> Result<(), Error> {
+        let interval = match self.autosave_interval {
+            Some(interval) => interval,
+            None => return Ok(()),
+        };
+
+        self.frames_since_autosave += 1;
+        if self.frames_since_autosave < interval {
+            return Ok(());
+        }
+
+        self.frames_since_autosave = 0;
+        self.store(Location::Autosave, save(state))
+    }
+
+    pub fn load_autosave(&self) -> Result<State, Error> {
+        self.fetch(Location::Autosave)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Error, Manager};
+    use crate::interp::state::State;
+    use std::fs;
+
+    fn state_with_pc(pc: u16) -> State {
+        let mut st = State::new_undefined();
+        st.pc = pc;
+        st
+    }
+
+    #[test]
+    fn slots_in_memory() {
+        let mut m = Manager::new(2);
+        m.save_slot(1, &state_with_pc(0x1234)).unwrap();
+        assert_eq!(m.load_slot(1).unwrap().pc, 0x1234);
+        assert!(matches!(m.load_slot(0), Err(Error::Empty)));
+    }
+
+    #[test]
+    fn invalid_slot() {
+        let mut m = Manager::new(2);
+        let st = State::new_undefined();
+        assert!(matches!(m.save_slot(2, &st), Err(Error::InvalidSlot(2))));
+        assert!(matches!(m.load_slot(5), Err(Error::InvalidSlot(5))));
+    }
+
+    #[test]
+    fn quick_state() {
+        let mut m = Manager::new(0);
+        assert!(matches!(m.quick_load(), Err(Error::Empty)));
+        m.quick_save(&state_with_pc(0x8000));
+        m.quick_save(&state_with_pc(0x8001));
+        assert_eq!(m.quick_load().unwrap().pc, 0x8001);
+    }
+
+    #[test]
+    fn autosave_interval() {
+        let mut m = Manager::new(0);
+        m.set_autosave_interval(Some(3));
+        m.on_frame(&state_with_pc(1)).unwrap();
+        m.on_frame(&state_with_pc(2)).unwrap();
+        assert!(matches!(m.load_autosave(), Err(Error::Empty)));
+        m.on_frame(&state_with_pc(3)).unwrap();
+        assert_eq!(m.load_autosave().unwrap().pc, 3);
+        m.on_frame(&state_with_pc(4)).unwrap();
+        assert_eq!(m.load_autosave().unwrap().pc, 3);
+    }
+
+    #[test]
+    fn autosave_disabled() {
+        let mut m = Manager::new(0);
+        m.on_frame(&state_with_pc(1)).unwrap();
+        assert!(matches!(m.load_autosave(), Err(Error::Empty)));
+    }
+
+    #[test]
+    fn file_backed_slots() {
+        let dir = std::env::temp_dir().join(format!("nesem-savestate-{}", std::process::id()));
+        let mut m = Manager::file_backed(3, dir.clone());
+        assert!(matches!(m.load_slot(0), Err(Error::Empty)));
+        m.save_slot(0, &state_with_pc(0xBEEF)).unwrap();
+
+        // a fresh manager over the same directory sees the slot
+        let m = Manager::file_backed(3, dir.clone());
+        assert_eq!(m.load_slot(0).unwrap().pc, 0xBEEF);
+        assert!(!dir.join("slot0.state.tmp").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
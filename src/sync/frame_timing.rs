@@ -0,0 +1,149 @@
+use std::time::Duration;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Region {
+    Ntsc,
+    Pal,
+}
+
+/// Exact fraction `num / den`, always in lowest terms with a nonzero denominator
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Ratio {
+    num: u64,
+    den: u64,
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+impl Ratio {
+    /// Create a fraction reduced to lowest terms, None if @den is zero
+    pub fn new(num: u64, den: u64) -> Option<Ratio> {
+        if den == 0 {
+            return None;
+        }
+        let d = gcd(num, den);
+        Some(Ratio {
+            num: num / d,
+            den: den / d,
+        })
+    }
+
+    pub fn num(self) -> u64 {
+        self.num
+    }
+
+    pub fn den(self) -> u64 {
+        self.den
+    }
+
+    /// Return `den / num`, None if the ratio is zero
+    pub fn recip(self) -> Option<Ratio> {
+        Ratio::new(self.den, self.num)
+    }
+
+    pub fn as_f64(self) -> f64 {
+        self.num as f64 / self.den as f64
+    }
+
+    /// Interpret the ratio as seconds and return @n times that duration, rounded down to ns
+    pub fn times_as_duration(self, n: u64) -> Duration {
+        let nanos = n as u128 * self.num as u128 * 1_000_000_000 / self.den as u128;
+        Duration::new(
+            (nanos / 1_000_000_000) as u64,
+            (nanos % 1_000_000_000) as u32,
+        )
+    }
+}
+
+/// Return number of frames per second in @region
+///
+/// NTSC: PPU runs at 236.25 MHz / 11 / 4 and a frame has 341 * 262 dots,
+/// minus the dot skipped on every other frame, i.e. 89341.5 dots on average (~60.0988 Hz).
+/// PAL: PPU runs at 26.601712 MHz / 5 and a frame has 341 * 312 dots (~50.0070 Hz).
+pub fn frame_rate(region: Region) -> Ratio {
+    let rate = match region {
+        // 236_250_000 / 44 / 89341.5
+        Region::Ntsc => Ratio::new(236_250_000 * 2, 44 * 178_683),
+        Region::Pal => Ratio::new(26_601_712, 5 * 341 * 312),
+    };
+    rate.expect("frame_rate: dots per frame are nonzero")
+}
+
+/// Return duration of one frame in @region, in seconds
+pub fn frame_duration(region: Region) -> Ratio {
+    frame_rate(region)
+        .recip()
+        .expect("frame_duration: frame rate is nonzero")
+}
+
+/// Return how long a frontend should sleep after emulating @frames frames in @elapsed time
+/// Target time is computed from the start of the run, so rounding errors don't accumulate.
+/// Returns zero when emulation is behind schedule.
+pub fn sleep_time(region: Region, frames: u64, elapsed: Duration) -> Duration {
+    let target = frame_duration(region).times_as_duration(frames);
+    target.checked_sub(elapsed).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{frame_duration, frame_rate, sleep_time, Ratio, Region};
+    use std::time::Duration;
+
+    #[test]
+    fn ntsc_rate() {
+        let r = frame_rate(Region::Ntsc);
+        assert_eq!(r, Ratio::new(39_375_000, 655_171).unwrap());
+        assert!((r.as_f64() - 60.0988).abs() < 1e-4);
+    }
+
+    #[test]
+    fn pal_rate() {
+        let r = frame_rate(Region::Pal);
+        assert!((r.as_f64() - 50.0070).abs() < 1e-4);
+    }
+
+    #[test]
+    fn ratio_is_reduced() {
+        assert_eq!(Ratio::new(10, 4), Some(Ratio { num: 5, den: 2 }));
+        assert_eq!(Ratio::new(0, 4), Some(Ratio { num: 0, den: 1 }));
+    }
+
+    #[test]
+    fn zero_denominator() {
+        assert_eq!(Ratio::new(0, 0), None);
+        assert_eq!(Ratio::new(3, 0), None);
+        assert_eq!(Ratio::new(0, 4).unwrap().recip(), None);
+        let r = Ratio::new(4, 6).unwrap().recip().unwrap();
+        assert_eq!((r.num(), r.den()), (3, 2));
+    }
+
+    #[test]
+    fn duration_of_many_frames() {
+        let d = frame_duration(Region::Ntsc);
+        // 39375000 frames take exactly 655171 seconds
+        assert_eq!(
+            d.times_as_duration(39_375_000),
+            Duration::from_secs(655_171)
+        );
+        assert_eq!(d.times_as_duration(1), Duration::from_nanos(16_639_263));
+    }
+
+    #[test]
+    fn sleep_time_ahead_and_behind() {
+        let frame = frame_duration(Region::Pal).times_as_duration(1);
+        assert_eq!(
+            sleep_time(Region::Pal, 1, Duration::from_millis(5)),
+            frame - Duration::from_millis(5)
+        );
+        assert_eq!(
+            sleep_time(Region::Pal, 1, Duration::from_secs(1)),
+            Duration::from_secs(0)
+        );
+    }
+}
@@ -0,0 +1,2 @@
+pub mod frame_timing;
+pub mod rate_control;
@@ -0,0 +1,95 @@
+/// Dynamic rate control
+/// See https://github.com/libretro/docs/blob/master/archive/ratecontrol.pdf
+///
+/// The host plays audio at a slightly different rate than the emulator produces it,
+/// so the host buffer slowly drains or overfills. Instead of dropping or repeating
+/// samples, the resampling ratio is nudged by at most `max_delta` depending on how
+/// full the host buffer currently is.
+pub struct RateControl {
+    /// Sample rate of the emulated audio
+    input_rate: f64,
+    /// Sample rate of the host audio device
+    output_rate: f64,
+    /// Maximum relative deviation from the nominal ratio, e.g. `0.005` for 0.5%
+    max_delta: f64,
+}
+
+impl RateControl {
+    pub fn new(input_rate: f64, output_rate: f64, max_delta: f64) -> RateControl {
+        RateControl {
+            input_rate,
+            output_rate,
+            max_delta,
+        }
+    }
+
+    /// Resampling ratio (output samples per input sample) without any correction
+    pub fn nominal_ratio(&self) -> f64 {
+        self.output_rate / self.input_rate
+    }
+
+    /// Return the relative correction for a host buffer holding @fill samples out of @capacity
+    /// Half-full buffer => 1.0, empty buffer => 1 + max_delta, full buffer => 1 - max_delta
+    fn correction(&self, fill: usize, capacity: usize) -> f64 {
+        if capacity == 0 {
+            return 1.0;
+        }
+
+        let level = (fill as f64 / capacity as f64).min(1.0);
+        1.0 + (1.0 - 2.0 * level) * self.max_delta
+    }
+
+    /// Return the resampling ratio to use for the next batch of samples
+    /// An emptier buffer produces more output samples per input sample
+    pub fn ratio(&self, fill: usize, capacity: usize) -> f64 {
+        self.nominal_ratio() * self.correction(fill, capacity)
+    }
+
+    /// Return the factor to multiply the nominal frame duration with
+    /// For frontends which pace emulation by audio: an emptier buffer makes frames shorter
+    pub fn frame_time_scale(&self, fill: usize, capacity: usize) -> f64 {
+        1.0 / self.correction(fill, capacity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RateControl;
+
+    fn approx_eq(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn half_full_is_nominal() {
+        let rc = RateControl::new(48000.0, 44100.0, 0.005);
+        assert!(approx_eq(rc.ratio(512, 1024), 44100.0 / 48000.0));
+        assert!(approx_eq(rc.frame_time_scale(512, 1024), 1.0));
+    }
+
+    #[test]
+    fn empty_buffer_speeds_up() {
+        let rc = RateControl::new(44100.0, 44100.0, 0.005);
+        assert!(approx_eq(rc.ratio(0, 1024), 1.005));
+        assert!(rc.frame_time_scale(0, 1024) < 1.0);
+    }
+
+    #[test]
+    fn full_buffer_slows_down() {
+        let rc = RateControl::new(44100.0, 44100.0, 0.005);
+        assert!(approx_eq(rc.ratio(1024, 1024), 0.995));
+        assert!(rc.frame_time_scale(1024, 1024) > 1.0);
+    }
+
+    #[test]
+    fn overfull_buffer_is_clamped() {
+        let rc = RateControl::new(44100.0, 44100.0, 0.005);
+        assert!(approx_eq(rc.ratio(4096, 1024), 0.995));
+    }
+
+    #[test]
+    fn zero_capacity_is_nominal() {
+        let rc = RateControl::new(44100.0, 48000.0, 0.005);
+        assert!(approx_eq(rc.ratio(0, 0), rc.nominal_ratio()));
+    }
+}
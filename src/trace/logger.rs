@@ -0,0 +1,152 @@
+use std::fmt;
+use std::io::Write;
+
+/// Subsystem a log record comes from
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Target {
+    /// Executed instructions and interrupts
+    Cpu,
+    /// Interpreter bookkeeping, e.g. resets and tolerated errors
+    Interp,
+}
+
+impl Target {
+    pub fn name(self) -> &'static str {
+        match self {
+            Target::Cpu => "cpu",
+            Target::Interp => "interp",
+        }
+    }
+}
+
+/// Severity of a log record, most severe first
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    /// One record per executed instruction
+    Trace,
+}
+
+impl Level {
+    pub fn name(self) -> &'static str {
+        match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        }
+    }
+}
+
+/// A single diagnostic, @args are only formatted by loggers which want it
+pub struct Record<'a> {
+    pub target: Target,
+    pub level: Level,
+    pub args: fmt::Arguments<'a>,
+}
+
+/// Receives diagnostics of one interpreter, see `State::set_logger`
+/// Implement this to forward records to the logging pipeline of the host application.
+/// Records are only produced with the `trace` feature enabled.
+pub trait Logger: Send {
+    /// Return false to skip building records of @level from @target
+    fn enabled(&self, target: Target, level: Level) -> bool;
+
+    fn log(&mut self, record: &Record);
+}
+
+/// Writes records as lines of text to @W, e.g. `[cpu TRACE] 0600  A9 05 ...`
+/// Each target has its own maximum level, records less severe than that are dropped
+pub struct WriteLogger<W: Write + Send> {
+    writer: W,
+    cpu: Option<Level>,
+    interp: Option<Level>,
+}
+
+impl<W: Write + Send> WriteLogger<W> {
+    /// Create a logger writing to @writer with every target at `Level::Info`
+    pub fn new(writer: W) -> WriteLogger<W> {
+        WriteLogger {
+            writer,
+            cpu: Some(Level::Info),
+            interp: Some(Level::Info),
+        }
+    }
+
+    /// Keep records of @target up to @level, None silences the target
+    pub fn with_level(mut self, target: Target, level: Option<Level>) -> WriteLogger<W> {
+        match target {
+            Target::Cpu => self.cpu = level,
+            Target::Interp => self.interp = level,
+        }
+        self
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write + Send> Logger for WriteLogger<W> {
+    fn enabled(&self, target: Target, level: Level) -> bool {
+        let max = match target {
+            Target::Cpu => self.cpu,
+            Target::Interp => self.interp,
+        };
+        matches!(max, Some(max) if level <= max)
+    }
+
+    fn log(&mut self, record: &Record) {
+        if !self.enabled(record.target, record.level) {
+            return;
+        }
+        // a broken log sink must not stop the emulation
+        let _ = writeln!(
+            self.writer,
+            "[{} {}] {}",
+            record.target.name(),
+            record.level.name(),
+            record.args
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Level, Logger, Record, Target, WriteLogger};
+
+    fn log(logger: &mut WriteLogger<Vec<u8>>, target: Target, level: Level, text: &str) {
+        logger.log(&Record {
+            target,
+            level,
+            args: format_args!("{}", text),
+        });
+    }
+
+    #[test]
+    fn levels_per_target() {
+        let mut logger = WriteLogger::new(Vec::new())
+            .with_level(Target::Cpu, Some(Level::Trace))
+            .with_level(Target::Interp, Some(Level::Warn));
+        assert!(logger.enabled(Target::Cpu, Level::Trace));
+        assert!(!logger.enabled(Target::Interp, Level::Info));
+
+        log(&mut logger, Target::Cpu, Level::Trace, "lda");
+        log(&mut logger, Target::Interp, Level::Info, "reset");
+        log(&mut logger, Target::Interp, Level::Error, "oops");
+        let text = String::from_utf8(logger.into_inner()).unwrap();
+        assert_eq!(text, "[cpu TRACE] lda\n[interp ERROR] oops\n");
+    }
+
+    #[test]
+    fn silenced_target() {
+        let logger = WriteLogger::new(Vec::new()).with_level(Target::Cpu, None);
+        assert!(!logger.enabled(Target::Cpu, Level::Error));
+        assert!(logger.enabled(Target::Interp, Level::Info));
+        assert!(!logger.enabled(Target::Interp, Level::Debug));
+    }
+}
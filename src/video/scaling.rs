@@ -0,0 +1,212 @@
+/// Pixels the NES draws outside the area visible on most TVs, on each side
+pub const OVERSCAN: usize = 8;
+
+const BORDER: [u8; 4] = [0, 0, 0, 0xFF];
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Size {
+    pub width: usize,
+    pub height: usize,
+}
+
+/// Area of a target with its top left corner at @x, @y
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Rect {
+    pub x: usize,
+    pub y: usize,
+    pub size: Size,
+}
+
+/// RGBA image, 4 bytes per pixel, rows top to bottom
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+    pub size: Size,
+    pub pixels: Vec<u8>,
+}
+
+/// How a frame is fitted into a target of another size
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ScalingMode {
+    /// Largest whole multiple of the frame size which fits, sharp pixels
+    /// Falls back to `Fit` for targets smaller than the frame
+    Integer,
+    /// Largest size of the same aspect ratio which fits
+    Fit,
+    /// Fill the whole target, aspect ratio is not kept
+    Stretch,
+}
+
+/// Return where a frame of @source size is drawn in @target according to @mode
+/// The area is centered, the rest of the target is border
+pub fn viewport(source: Size, target: Size, mode: ScalingMode) -> Rect {
+    let size = match mode {
+        ScalingMode::Stretch => target,
+        _ if source.width == 0 || source.height == 0 => Size {
+            width: 0,
+            height: 0,
+        },
+        ScalingMode::Integer if target.width >= source.width && target.height >= source.height => {
+            let scale = (target.width / source.width).min(target.height / source.height);
+            Size {
+                width: source.width * scale,
+                height: source.height * scale,
+            }
+        }
+        _ => {
+            let scale = (target.width as f64 / source.width as f64)
+                .min(target.height as f64 / source.height as f64);
+            Size {
+                width: ((source.width as f64 * scale).round() as usize).min(target.width),
+                height: ((source.height as f64 * scale).round() as usize).min(target.height),
+            }
+        }
+    };
+    Rect {
+        x: (target.width - size.width) / 2,
+        y: (target.height - size.height) / 2,
+        size,
+    }
+}
+
+/// Return @frame without `OVERSCAN` pixels on each side
+/// Frames too small to crop are returned unchanged
+pub fn crop_overscan(frame: &Frame) -> Frame {
+    let Size { width, height } = frame.size;
+    if width <= 2 * OVERSCAN || height <= 2 * OVERSCAN {
+        return frame.clone();
+    }
+    let size = Size {
+        width: width - 2 * OVERSCAN,
+        height: height - 2 * OVERSCAN,
+    };
+    let mut pixels = Vec::with_capacity(size.width * size.height * 4);
+    for y in OVERSCAN..height - OVERSCAN {
+        let start = (y * width + OVERSCAN) * 4;
+        pixels.extend_from_slice(&frame.pixels[start..start + size.width * 4]);
+    }
+    Frame { size, pixels }
+}
+
+/// Scale @frame into a new frame of @target size according to @mode, nearest neighbor
+/// With @crop, the overscan is removed first, see `crop_overscan`
+pub fn present(frame: &Frame, target: Size, mode: ScalingMode, crop: bool) -> Frame {
+    let cropped;
+    let frame = if crop {
+        cropped = crop_overscan(frame);
+        &cropped
+    } else {
+        frame
+    };
+    let area = viewport(frame.size, target, mode);
+    let mut pixels: Vec<u8> = BORDER
+        .iter()
+        .copied()
+        .cycle()
+        .take(target.width * target.height * 4)
+        .collect();
+    for y in 0..area.size.height {
+        let src_y = y * frame.size.height / area.size.height;
+        for x in 0..area.size.width {
+            let src_x = x * frame.size.width / area.size.width;
+            let src = (src_y * frame.size.width + src_x) * 4;
+            let dst = ((area.y + y) * target.width + area.x + x) * 4;
+            pixels[dst..dst + 4].copy_from_slice(&frame.pixels[src..src + 4]);
+        }
+    }
+    Frame {
+        size: target,
+        pixels,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{crop_overscan, present, viewport, Frame, Rect, ScalingMode, Size};
+
+    const NES: Size = Size {
+        width: 256,
+        height: 240,
+    };
+
+    fn size(width: usize, height: usize) -> Size {
+        Size { width, height }
+    }
+
+    fn rect(x: usize, y: usize, width: usize, height: usize) -> Rect {
+        Rect {
+            x,
+            y,
+            size: size(width, height),
+        }
+    }
+
+    /// Frame where every pixel holds its own coordinates
+    fn numbered(width: usize, height: usize) -> Frame {
+        let mut pixels = Vec::new();
+        for y in 0..height {
+            for x in 0..width {
+                pixels.extend_from_slice(&[x as u8, y as u8, 0, 0xFF]);
+            }
+        }
+        Frame {
+            size: size(width, height),
+            pixels,
+        }
+    }
+
+    #[test]
+    fn viewports() {
+        let target = size(800, 600);
+        assert_eq!(
+            viewport(NES, target, ScalingMode::Integer),
+            rect(144, 60, 512, 480)
+        );
+        assert_eq!(
+            viewport(NES, target, ScalingMode::Fit),
+            rect(80, 0, 640, 600)
+        );
+        assert_eq!(
+            viewport(NES, target, ScalingMode::Stretch),
+            rect(0, 0, 800, 600)
+        );
+        // too small for a whole multiple
+        assert_eq!(
+            viewport(NES, size(128, 128), ScalingMode::Integer),
+            rect(0, 4, 128, 120)
+        );
+    }
+
+    #[test]
+    fn crop() {
+        let frame = crop_overscan(&numbered(256, 240));
+        assert_eq!(frame.size, size(240, 224));
+        assert_eq!(frame.pixels[..4], [8, 8, 0, 0xFF]);
+        let tiny = numbered(4, 4);
+        assert_eq!(crop_overscan(&tiny), tiny);
+    }
+
+    #[test]
+    fn integer_scaling() {
+        let out = present(&numbered(2, 2), size(5, 4), ScalingMode::Integer, false);
+        let pixel = |x: usize, y: usize| out.pixels[(y * 5 + x) * 4..(y * 5 + x) * 4 + 2].to_vec();
+        // 4x4 image with a one pixel border on the right
+        assert_eq!(pixel(0, 0), vec![0, 0]);
+        assert_eq!(pixel(1, 1), vec![0, 0]);
+        assert_eq!(pixel(2, 1), vec![1, 0]);
+        assert_eq!(pixel(3, 3), vec![1, 1]);
+        assert_eq!(out.pixels[4 * 4..5 * 4], [0, 0, 0, 0xFF]);
+    }
+
+    #[test]
+    fn cropped_present() {
+        let out = present(
+            &numbered(256, 240),
+            size(480, 448),
+            ScalingMode::Integer,
+            true,
+        );
+        assert_eq!(out.pixels[..4], [8, 8, 0, 0xFF]);
+        let last = out.pixels.len() - 4;
+        assert_eq!(out.pixels[last..], [247, 231, 0, 0xFF]);
+    }
+}